@@ -0,0 +1,264 @@
+use crate::backend::Backend;
+use crate::models::{Color, Rect, SizeHints};
+use crate::window_system::Atoms;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use x11::xlib::{Atom, KeySym, Window};
+
+/// A request the window manager made to the backend
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Call {
+    MoveResize {
+        window: Window,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    },
+    Map(Window),
+    Unmap(Window),
+    Raise(Window),
+    Focus(Window),
+    CreateFrame(Window),
+    DestroyWindow(Window),
+    Reparent {
+        window: Window,
+        parent: Window,
+        x: i32,
+        y: i32,
+    },
+    AddToSaveSet(Window),
+    RemoveFromSaveSet(Window),
+    SetBorderWidth(Window, i32),
+    SetBorderColor(Window, Color),
+    ConfigureNotify(Window, Rect),
+    WatchProperties(Window),
+    LoadFont(String),
+    DrawTitleBar {
+        frame: Window,
+        width: i32,
+        height: i32,
+        title: String,
+        background: Color,
+        foreground: Color,
+    },
+    Kill(Window),
+    GrabKey {
+        keycode: u32,
+        mask: u32,
+        window: Window,
+    },
+    UngrabKey {
+        keycode: u32,
+        mask: u32,
+        window: Window,
+    },
+    SetTextProperty(Window, Atom, String),
+    SendProtocol(Window, Atom),
+}
+
+/// An in-memory backend which just records what it was asked to do
+pub struct FakeBackend {
+    pub width: i32,
+    pub height: i32,
+    pub atoms: Atoms,
+    /// The WM_PROTOCOLS of every window
+    pub protocols: HashMap<Window, Vec<Atom>>,
+    /// The windows with the urgency hint set
+    pub urgent: HashSet<Window>,
+    pub titles: HashMap<Window, String>,
+    pub transients: HashMap<Window, Window>,
+    pub geometries: HashMap<Window, Rect>,
+    pub size_hints: HashMap<Window, SizeHints>,
+    /// Keysyms sharing the keycode of another keysym (like `Q` with `q`)
+    pub keycodes: HashMap<KeySym, u32>,
+    /// The id the next frame gets
+    next_frame: Cell<Window>,
+    pub calls: RefCell<Vec<Call>>,
+}
+
+pub const ROOT: Window = 1;
+/// Frames are numbered from here on, to keep them apart from client windows
+pub const FIRST_FRAME: Window = 1000;
+
+impl FakeBackend {
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            atoms: Atoms {
+                wm_protocols: 100,
+                wm_delete_window: 101,
+                wm_selection: 102,
+                manager: 103,
+                utf8_string: 104,
+                net_wm_name: 105,
+                simple_wm_layout: 106,
+            },
+            protocols: HashMap::new(),
+            urgent: HashSet::new(),
+            titles: HashMap::new(),
+            transients: HashMap::new(),
+            geometries: HashMap::new(),
+            size_hints: HashMap::new(),
+            keycodes: HashMap::new(),
+            next_frame: Cell::new(FIRST_FRAME),
+            calls: RefCell::new(vec![]),
+        }
+    }
+
+    /// Hands out all recorded calls, forgetting about them
+    pub fn take_calls(&self) -> Vec<Call> {
+        self.calls.replace(vec![])
+    }
+
+    fn record(&self, call: Call) {
+        self.calls.borrow_mut().push(call);
+    }
+}
+
+impl Backend for FakeBackend {
+    fn root(&self) -> Window {
+        ROOT
+    }
+    fn screen_size(&self) -> (i32, i32) {
+        (self.width, self.height)
+    }
+    fn atoms(&self) -> &Atoms {
+        &self.atoms
+    }
+
+    fn move_resize(&self, window: Window, x: i32, y: i32, width: i32, height: i32) {
+        self.record(Call::MoveResize {
+            window,
+            x,
+            y,
+            width,
+            height,
+        });
+    }
+    fn map(&self, window: Window) {
+        self.record(Call::Map(window));
+    }
+    fn unmap(&self, window: Window) {
+        self.record(Call::Unmap(window));
+    }
+    fn raise(&self, window: Window) {
+        self.record(Call::Raise(window));
+    }
+    fn focus(&self, window: Window) {
+        self.record(Call::Focus(window));
+    }
+    fn kill(&self, window: Window) {
+        self.record(Call::Kill(window));
+    }
+
+    fn create_frame(&self, _border_width: i32) -> Window {
+        let frame = self.next_frame.get();
+        self.next_frame.set(frame + 1);
+        self.record(Call::CreateFrame(frame));
+        frame
+    }
+    fn destroy_window(&self, window: Window) {
+        self.record(Call::DestroyWindow(window));
+    }
+    fn reparent(&self, window: Window, parent: Window, x: i32, y: i32) {
+        self.record(Call::Reparent {
+            window,
+            parent,
+            x,
+            y,
+        });
+    }
+    fn add_to_save_set(&self, window: Window) {
+        self.record(Call::AddToSaveSet(window));
+    }
+    fn remove_from_save_set(&self, window: Window) {
+        self.record(Call::RemoveFromSaveSet(window));
+    }
+    fn set_border_width(&self, window: Window, width: i32) {
+        self.record(Call::SetBorderWidth(window, width));
+    }
+    fn set_border_color(&self, window: Window, color: Color) {
+        self.record(Call::SetBorderColor(window, color));
+    }
+    fn send_configure_notify(&self, window: Window, bounds: Rect) {
+        self.record(Call::ConfigureNotify(window, bounds));
+    }
+    fn watch_properties(&self, window: Window) {
+        self.record(Call::WatchProperties(window));
+    }
+    fn is_urgent(&self, window: Window) -> bool {
+        self.urgent.contains(&window)
+    }
+    fn transient_for(&self, window: Window) -> Option<Window> {
+        self.transients.get(&window).cloned()
+    }
+    fn window_geometry(&self, window: Window) -> Option<Rect> {
+        self.geometries.get(&window).cloned()
+    }
+    fn size_hints(&self, window: Window) -> SizeHints {
+        self.size_hints.get(&window).cloned().unwrap_or_default()
+    }
+    fn window_title(&self, window: Window) -> String {
+        self.titles.get(&window).cloned().unwrap_or_default()
+    }
+    fn load_font(&self, name: &str) -> bool {
+        self.record(Call::LoadFont(name.to_string()));
+        true
+    }
+    fn draw_title_bar(
+        &self,
+        frame: Window,
+        width: i32,
+        height: i32,
+        title: &str,
+        background: Color,
+        foreground: Color,
+    ) {
+        self.record(Call::DrawTitleBar {
+            frame,
+            width,
+            height,
+            title: title.to_string(),
+            background,
+            foreground,
+        });
+    }
+
+    /// Every keysym simply gets a keycode of the same value, unless it
+    /// shares the keycode of another one
+    fn keysym_to_keycode(&self, keysym: KeySym) -> u32 {
+        self.keycodes.get(&keysym).cloned().unwrap_or(keysym as u32)
+    }
+    fn keycode_to_keysym(&self, keycode: u32) -> KeySym {
+        keycode as KeySym
+    }
+    fn grab_key(&self, keycode: u32, mask: u32, window: Window) {
+        self.record(Call::GrabKey {
+            keycode,
+            mask,
+            window,
+        });
+    }
+    fn ungrab_key(&self, keycode: u32, mask: u32, window: Window) {
+        self.record(Call::UngrabKey {
+            keycode,
+            mask,
+            window,
+        });
+    }
+
+    fn set_text_property(&self, window: Window, property: Atom, value: &str) {
+        self.record(Call::SetTextProperty(window, property, value.to_string()));
+    }
+
+    fn supports_protocol(&self, window: Window, protocol: Atom) -> bool {
+        self.protocols
+            .get(&window)
+            .is_some_and(|protocols| protocols.contains(&protocol))
+    }
+    fn send_protocol(&self, window: Window, protocol: Atom) {
+        self.record(Call::SendProtocol(window, protocol));
+    }
+}
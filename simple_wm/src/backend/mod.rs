@@ -0,0 +1,84 @@
+#[cfg(test)]
+pub mod fake;
+
+use crate::models::{Color, Rect, SizeHints};
+use crate::window_system::Atoms;
+use x11::xlib::{Atom, KeySym, Window};
+
+/// Everything simple_wm needs from the display server.
+/// `WindowSystem` talks to X11, the tests use `fake::FakeBackend` instead
+pub trait Backend {
+    fn root(&self) -> Window;
+    /// The size of the screen in px
+    fn screen_size(&self) -> (i32, i32);
+    fn atoms(&self) -> &Atoms;
+
+    fn move_resize(&self, window: Window, x: i32, y: i32, width: i32, height: i32);
+    fn map(&self, window: Window);
+    fn unmap(&self, window: Window);
+    /// Puts the window on top of its siblings
+    fn raise(&self, window: Window);
+    fn focus(&self, window: Window);
+
+    /// Creates an (unmapped) frame window, which gets the substructure events of its client
+    fn create_frame(&self, border_width: i32) -> Window;
+    fn destroy_window(&self, window: Window);
+    fn reparent(&self, window: Window, parent: Window, x: i32, y: i32);
+    /// Windows in the save set survive the window manager exiting (or crashing)
+    fn add_to_save_set(&self, window: Window);
+    fn remove_from_save_set(&self, window: Window);
+    fn set_border_width(&self, window: Window, width: i32);
+    fn set_border_color(&self, window: Window, color: Color);
+    /// Tells a reparented client where it is on screen, as it only sees its frame moving
+    fn send_configure_notify(&self, window: Window, bounds: Rect);
+    /// Selects PropertyChangeMask on a client window
+    fn watch_properties(&self, window: Window);
+    /// Whether the urgency hint is set in WM_HINTS
+    fn is_urgent(&self, window: Window) -> bool;
+    /// The window this one is a dialog (or similar) for, from WM_TRANSIENT_FOR
+    fn transient_for(&self, window: Window) -> Option<Window>;
+    /// The current geometry of the window, without its border
+    fn window_geometry(&self, window: Window) -> Option<Rect>;
+    /// The size constraints from WM_NORMAL_HINTS
+    fn size_hints(&self, window: Window) -> SizeHints;
+    /// The title of the window, from _NET_WM_NAME or WM_NAME
+    fn window_title(&self, window: Window) -> String;
+    /// Loads the title bar font, given as Xft pattern like "monospace:size=10"
+    fn load_font(&self, name: &str) -> bool;
+    /// Fills the top of the frame with the background and writes the title onto it
+    fn draw_title_bar(
+        &self,
+        frame: Window,
+        width: i32,
+        height: i32,
+        title: &str,
+        background: Color,
+        foreground: Color,
+    );
+    /// Kills the whole X connection of the client owning the window
+    fn kill(&self, window: Window);
+
+    fn keysym_to_keycode(&self, keysym: KeySym) -> u32;
+    /// The keysym on the first level of the keycode (i.e. without Shift)
+    fn keycode_to_keysym(&self, keycode: u32) -> KeySym;
+    fn grab_key(&self, keycode: u32, mask: u32, window: Window);
+    fn ungrab_key(&self, keycode: u32, mask: u32, window: Window);
+
+    /// Sets a UTF8_STRING property of the window
+    fn set_text_property(&self, window: Window, property: Atom, value: &str);
+
+    /// Whether the window lists the protocol in its WM_PROTOCOLS property
+    fn supports_protocol(&self, window: Window, protocol: Atom) -> bool;
+    /// Sends a WM_PROTOCOLS client message, as described in the ICCCM
+    fn send_protocol(&self, window: Window, protocol: Atom);
+
+    /// Asks the window to close itself, only killing clients which don't support that
+    fn close(&self, window: Window) {
+        let delete_window = self.atoms().wm_delete_window;
+        if self.supports_protocol(window, delete_window) {
+            self.send_protocol(window, delete_window);
+        } else {
+            self.kill(window);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::env;
+use std::path::PathBuf;
+use std::process;
+
+/// The options simple_wm can be started with
+pub struct Args {
+    /// An explicit config file, overriding the default locations
+    pub config: Option<PathBuf>,
+    /// Replace an already running window manager instead of exiting
+    pub replace: bool,
+    /// The X display to connect to, instead of $DISPLAY
+    pub display: Option<String>,
+}
+
+impl Args {
+    pub fn from_env() -> Self {
+        let mut args = Self {
+            config: None,
+            replace: false,
+            display: None,
+        };
+        let mut iter = env::args().skip(1);
+
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "-c" | "--config" => match iter.next() {
+                    Some(path) => args.config = Some(PathBuf::from(path)),
+                    None => Self::fail("--config requires a path"),
+                },
+                "-d" | "--display" => match iter.next() {
+                    Some(display) => args.display = Some(display),
+                    None => Self::fail("--display requires a display name"),
+                },
+                "--replace" => args.replace = true,
+                "-h" | "--help" => {
+                    println!("{}", Self::usage());
+                    process::exit(0);
+                }
+                other => Self::fail(&format!("unknown argument '{}'", other)),
+            }
+        }
+        args
+    }
+
+    fn usage() -> &'static str {
+        "usage: simple_wm [--config <path>] [--display <name>] [--replace]"
+    }
+
+    fn fail(message: &str) -> ! {
+        eprintln!("simple_wm: {}\n{}", message, Self::usage());
+        process::exit(2);
+    }
+}
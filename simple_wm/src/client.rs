@@ -1,12 +1,34 @@
-use crate::window_system::WindowSystem;
+use crate::backend::Backend;
+use crate::models::{Color, Rect, SizeHints};
 use x11::xlib;
 
 /// In simple_wm, a client basically represents the entire window a client sees,
 /// that is: The actual window + the frame (as we're reparenting) to draw a border
+/// and optionally a title bar above the window
 #[derive(Eq, PartialEq, Debug, Hash, Clone)]
 pub struct Client {
     /// This is the actual window which stems from the client
     pub window: xlib::Window,
+    /// The window we reparent the client into, 0 as long as there is none
+    pub frame: xlib::Window,
+    pub border_width: i32,
+    /// The border color last set on the frame
+    pub border_color: Option<Color>,
+    /// Whether the client asks for attention via WM_HINTS
+    pub urgent: bool,
+    /// The title shown in the title bar
+    pub title: String,
+    /// The height of the title bar, 0 without one
+    pub title_height: i32,
+    /// The text color the title was last drawn with
+    pub title_color: Option<Color>,
+    /// Floating clients keep their own geometry instead of being laid out
+    pub floating: bool,
+    /// The geometry the client had when it last stopped floating
+    pub floating_bounds: Option<Rect>,
+    /// The size constraints of the window from WM_NORMAL_HINTS
+    pub size_hints: SizeHints,
+    /// The outer geometry of the frame, including its border & title bar
     pub x: i32,
     pub y: i32,
     pub width: i32,
@@ -14,9 +36,19 @@ pub struct Client {
 }
 
 impl Client {
-    pub fn new(conf_event: xlib::XConfigureRequestEvent) -> Self {
+    pub fn from_window(window: xlib::Window) -> Self {
         Self {
-            window: conf_event.window,
+            window,
+            frame: 0,
+            border_width: 0,
+            border_color: None,
+            urgent: false,
+            title: String::new(),
+            title_height: 0,
+            title_color: None,
+            floating: false,
+            floating_bounds: None,
+            size_hints: SizeHints::default(),
             x: 0,
             y: 0,
             width: 0,
@@ -24,51 +56,167 @@ impl Client {
         }
     }
 
-    pub fn set_bounds(
-        &mut self,
-        window_system: &WindowSystem,
-        x: i32,
-        y: i32,
-        width: i32,
-        height: i32,
-    ) {
-        self.x = x;
-        self.y = y;
-        self.width = width;
-        self.height = height;
+    /// Creates the frame and moves the window into it, below the title bar
+    pub fn reparent(&mut self, window_system: &dyn Backend, border_width: i32, title_height: i32) {
+        self.border_width = border_width;
+        self.title_height = title_height;
+        self.frame = window_system.create_frame(border_width);
+        // if we die, the window is put back onto the root window instead of being destroyed
+        window_system.add_to_save_set(self.window);
+        window_system.reparent(self.window, self.frame, 0, title_height);
+    }
 
-        self.move_window(window_system);
+    /// Destroys the frame. Unless the window is already gone,
+    /// it is put back onto the root window where the frame was
+    pub fn unparent(&mut self, window_system: &dyn Backend, destroyed: bool) {
+        if !destroyed {
+            let root = window_system.root();
+            let bounds = self.window_bounds();
+            window_system.reparent(self.window, root, bounds.x, bounds.y);
+            window_system.remove_from_save_set(self.window);
+        }
+        window_system.destroy_window(self.frame);
+        self.frame = 0;
+    }
+
+    pub fn bounds(&self) -> Rect {
+        Rect::new(self.x, self.y, self.width, self.height)
+    }
+
+    /// Where the actual window is on screen, i.e. within the border & below the title bar
+    pub fn window_bounds(&self) -> Rect {
+        let border = self.border_width;
+        Rect::new(
+            self.x + border,
+            self.y + border + self.title_height,
+            (self.width - 2 * border).max(1),
+            (self.height - 2 * border - self.title_height).max(1),
+        )
     }
 
-    pub fn move_horizontal(&mut self, amount: i32, move_x: bool, window_system: &WindowSystem) {
-        self.width += amount;
-        if move_x {
-            self.x += amount;
+    /// The outer geometry of the frame around a window with the given geometry
+    pub fn frame_bounds(&self, window: Rect) -> Rect {
+        let border = self.border_width;
+        Rect::new(
+            window.x - border,
+            window.y - border - self.title_height,
+            window.width + 2 * border,
+            window.height + 2 * border + self.title_height,
+        )
+    }
+
+    /// Resizes the frame bounds so that the window within honors its size hints,
+    /// keeping the top left corner in place
+    pub fn constrain(&self, bounds: Rect) -> Rect {
+        let decoration_width = 2 * self.border_width;
+        let decoration_height = 2 * self.border_width + self.title_height;
+        let (width, height) = self.size_hints.apply(
+            bounds.width - decoration_width,
+            bounds.height - decoration_height,
+        );
+        Rect::new(
+            bounds.x,
+            bounds.y,
+            width + decoration_width,
+            height + decoration_height,
+        )
+    }
+
+    /// Moves & resizes the window, unless it is already there
+    pub fn set_bounds(&mut self, window_system: &dyn Backend, bounds: Rect) {
+        if bounds == self.bounds() {
+            return;
         }
+        self.x = bounds.x;
+        self.y = bounds.y;
+        self.width = bounds.width;
+        self.height = bounds.height;
+
         self.move_window(window_system);
     }
 
-    fn move_window(&self, window_system: &WindowSystem) {
-        unsafe {
-            xlib::XMoveResizeWindow(
-                window_system.display,
-                self.window,
-                self.x,
-                self.y,
-                self.width as u32,
-                self.height as u32,
-            );
+    /// Changes the border width & title bar height, keeping the outer geometry
+    pub fn set_decoration(
+        &mut self,
+        window_system: &dyn Backend,
+        border_width: i32,
+        title_height: i32,
+    ) {
+        if border_width == self.border_width && title_height == self.title_height {
+            return;
+        }
+        if border_width != self.border_width {
+            self.border_width = border_width;
+            window_system.set_border_width(self.frame, border_width);
         }
+        self.title_height = title_height;
+        self.move_window(window_system);
     }
 
-    pub fn map(&self, window_system: &WindowSystem) {
-        unsafe {
-            xlib::XMapWindow(window_system.display, self.window);
+    /// Changes the border color, unless it already has that color.
+    /// Returns whether the color changed
+    pub fn set_border_color(&mut self, window_system: &dyn Backend, color: Color) -> bool {
+        if self.border_color == Some(color) {
+            return false;
         }
+        self.border_color = Some(color);
+        window_system.set_border_color(self.frame, color);
+        true
     }
-    pub fn unmap(&self, window_system: &WindowSystem) {
-        unsafe {
-            xlib::XUnmapWindow(window_system.display, self.window);
+
+    /// Draws the title bar (if any) in the border color, writing the title in `text_color`
+    pub fn draw_title_bar(&mut self, window_system: &dyn Backend, text_color: Color) {
+        if self.title_height == 0 {
+            return;
         }
+        if let Some(background) = self.border_color {
+            self.title_color = Some(text_color);
+            window_system.draw_title_bar(
+                self.frame,
+                self.window_bounds().width,
+                self.title_height,
+                &self.title,
+                background,
+                text_color,
+            );
+        }
+    }
+
+    /// The center of the client on screen
+    pub fn center(&self) -> (i32, i32) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    /// Whether both clients share some rows on screen
+    pub fn overlaps_vertically(&self, other: &Client) -> bool {
+        self.y < other.y + other.height && other.y < self.y + self.height
+    }
+
+    /// Whether both clients share some columns on screen
+    pub fn overlaps_horizontally(&self, other: &Client) -> bool {
+        self.x < other.x + other.width && other.x < self.x + self.width
+    }
+
+    fn move_window(&self, window_system: &dyn Backend) {
+        // X draws the border outside of the frame, the title bar is part of it
+        let inner = self.window_bounds();
+        let frame_height = inner.height + self.title_height;
+        window_system.move_resize(self.frame, self.x, self.y, inner.width, frame_height);
+        window_system.move_resize(self.window, 0, self.title_height, inner.width, inner.height);
+        self.configure_notify(window_system);
+    }
+
+    /// Tells the client where it is on screen
+    pub fn configure_notify(&self, window_system: &dyn Backend) {
+        window_system.send_configure_notify(self.window, self.window_bounds());
+    }
+
+    pub fn map(&self, window_system: &dyn Backend) {
+        window_system.map(self.window);
+        window_system.map(self.frame);
+    }
+    /// Hides the frame; the window itself stays mapped within it
+    pub fn unmap(&self, window_system: &dyn Backend) {
+        window_system.unmap(self.frame);
     }
 }
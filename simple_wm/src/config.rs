@@ -1,53 +1,379 @@
 use crate::key_handler::*;
+use crate::layout::LayoutKind;
+use crate::models::{Color, Direction};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use x11::keysym;
+
+/// The name of the config file within the simple_wm config directory
+const CONFIG_FILE_NAME: &str = "config.json";
+
+/// `split_ratio` is clamped to this range, so that both sides of a split keep some space
+const MIN_SPLIT_RATIO: f32 = 0.05;
+const MAX_SPLIT_RATIO: f32 = 0.95;
 
 #[derive(Deserialize, Serialize)]
+#[serde(default)]
 pub struct Config {
     /// The gaps in px
     pub gaps: usize,
     /// By how much do you want to shift your windows?
     pub shift_by: usize,
+    /// The width of the border around every client in px
+    pub border_width: usize,
+    pub border_color: Color,
+    pub focused_border_color: Color,
+    /// The border color of clients asking for attention
+    pub urgent_border_color: Color,
+    /// Draw a title bar showing the window title on top of every client
+    pub title_bars: bool,
+    /// The height of the title bars in px
+    pub title_bar_height: usize,
+    /// The Xft font of the titles, e.g. "monospace:size=10"
+    pub title_font: String,
+    /// The text color of the titles; the title bar itself takes the border color
+    pub title_color: Color,
+    pub focused_title_color: Color,
+    /// Reload the config automatically as soon as the file changes
+    pub watch: bool,
+    /// The layout the workspaces start with
+    pub layout: LayoutKind,
+    /// Overrides `layout` for single workspaces (counting from 0)
+    pub workspace_layouts: HashMap<usize, LayoutKind>,
+    /// The layouts `CycleLayout` switches between, in order
+    pub layouts: Vec<LayoutKind>,
+    /// How many clients share the master area when a workspace starts
+    pub master_count: usize,
+    /// Overrides `master_count` for single workspaces (counting from 0)
+    pub workspace_master_counts: HashMap<usize, usize>,
+    /// Which part of the remaining area the spiral & dwindle layouts give to each client
+    pub split_ratio: f32,
+    /// The spiral & dwindle layouts stack clients once they would get smaller than this (in px)
+    pub min_client_size: usize,
+    /// Honor the size hints (e.g. resize increments) of tiled clients as well, not only floating ones
+    pub tiled_size_hints: bool,
     pub key_bindings: HashMap<KeyCombination, Action>,
 }
 
+/// Everything that can go wrong while loading the config file
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file could not be read
+    Io { path: PathBuf, source: io::Error },
+    /// The file was read, but is not a valid config
+    Parse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, source } => {
+                write!(f, "could not read {}: {}", path.display(), source)
+            }
+            ConfigError::Parse {
+                path,
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "invalid config {}:{}:{}: {}",
+                path.display(),
+                line,
+                column,
+                message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Config {
     pub fn new() -> Self {
         let mut key_bindings = HashMap::new();
         key_bindings.insert(
-            KeyCombination {
-                modifiers: vec![Modifier::Alt, Modifier::Shift],
-                key: Key::XK_q,
-            },
+            KeyCombination::new(vec![Modifier::Alt, Modifier::Shift], keysym::XK_q.into()),
             Action::Builtin(BuiltinCommand::Close),
         );
         key_bindings.insert(
-            KeyCombination {
-                modifiers: vec![Modifier::Super],
-                key: Key::XK_h,
-            },
+            KeyCombination::new(
+                vec![Modifier::Alt, Modifier::Shift, Modifier::Control],
+                keysym::XK_q.into(),
+            ),
+            Action::Builtin(BuiltinCommand::ForceKill),
+        );
+        key_bindings.insert(
+            KeyCombination::new(vec![Modifier::Super], keysym::XK_h.into()),
             Action::Builtin(BuiltinCommand::MoveLeft),
         );
         key_bindings.insert(
-            KeyCombination {
-                modifiers: vec![Modifier::Super],
-                key: Key::XK_l,
-            },
+            KeyCombination::new(vec![Modifier::Super], keysym::XK_l.into()),
             Action::Builtin(BuiltinCommand::MoveRight),
         );
         key_bindings.insert(
-            KeyCombination {
-                modifiers: vec![Modifier::Alt, Modifier::Shift],
-                key: Key::XK_f,
-            },
+            KeyCombination::new(vec![Modifier::Alt, Modifier::Shift], keysym::XK_f.into()),
             Action::Custom(vec!["dolphin".to_owned()]),
         );
+        key_bindings.insert(
+            KeyCombination::new(vec![Modifier::Alt, Modifier::Shift], keysym::XK_r.into()),
+            Action::Builtin(BuiltinCommand::ReloadConfig),
+        );
+        key_bindings.insert(
+            KeyCombination::new(vec![Modifier::Super], keysym::XK_j.into()),
+            Action::Builtin(BuiltinCommand::FocusNext),
+        );
+        key_bindings.insert(
+            KeyCombination::new(vec![Modifier::Super], keysym::XK_k.into()),
+            Action::Builtin(BuiltinCommand::FocusPrevious),
+        );
+        key_bindings.insert(
+            KeyCombination::new(vec![Modifier::Super, Modifier::Shift], keysym::XK_j.into()),
+            Action::Builtin(BuiltinCommand::MoveDown),
+        );
+        key_bindings.insert(
+            KeyCombination::new(vec![Modifier::Super, Modifier::Shift], keysym::XK_k.into()),
+            Action::Builtin(BuiltinCommand::MoveUp),
+        );
+        key_bindings.insert(
+            KeyCombination::new(vec![Modifier::Super], keysym::XK_Return.into()),
+            Action::Builtin(BuiltinCommand::Zoom),
+        );
+        key_bindings.insert(
+            KeyCombination::new(vec![Modifier::Super], keysym::XK_Tab.into()),
+            Action::Builtin(BuiltinCommand::ViewLastWorkspace),
+        );
+        key_bindings.insert(
+            KeyCombination::new(vec![Modifier::Super], keysym::XK_space.into()),
+            Action::Builtin(BuiltinCommand::CycleLayout),
+        );
+        key_bindings.insert(
+            KeyCombination::new(vec![Modifier::Super], keysym::XK_t.into()),
+            Action::Builtin(BuiltinCommand::SetLayout(LayoutKind::Stack)),
+        );
+        key_bindings.insert(
+            KeyCombination::new(vec![Modifier::Super], keysym::XK_m.into()),
+            Action::Builtin(BuiltinCommand::SetLayout(LayoutKind::Monocle)),
+        );
+        key_bindings.insert(
+            KeyCombination::new(vec![Modifier::Super], keysym::XK_g.into()),
+            Action::Builtin(BuiltinCommand::SetLayout(LayoutKind::Grid)),
+        );
+        key_bindings.insert(
+            KeyCombination::new(vec![Modifier::Super], keysym::XK_i.into()),
+            Action::Builtin(BuiltinCommand::IncreaseMasterCount),
+        );
+        key_bindings.insert(
+            KeyCombination::new(vec![Modifier::Super], keysym::XK_d.into()),
+            Action::Builtin(BuiltinCommand::DecreaseMasterCount),
+        );
+        key_bindings.insert(
+            KeyCombination::new(
+                vec![Modifier::Super, Modifier::Shift],
+                keysym::XK_space.into(),
+            ),
+            Action::Builtin(BuiltinCommand::ToggleFloating),
+        );
+
+        // Super+arrows --> focus in that direction, with Shift --> swap in that direction
+        let arrows = [
+            (keysym::XK_Left, Direction::Left),
+            (keysym::XK_Right, Direction::Right),
+            (keysym::XK_Up, Direction::Up),
+            (keysym::XK_Down, Direction::Down),
+        ];
+        for (arrow, direction) in arrows.iter() {
+            key_bindings.insert(
+                KeyCombination::new(vec![Modifier::Super], (*arrow).into()),
+                Action::Builtin(BuiltinCommand::FocusDirection(*direction)),
+            );
+            key_bindings.insert(
+                KeyCombination::new(vec![Modifier::Super, Modifier::Shift], (*arrow).into()),
+                Action::Builtin(BuiltinCommand::SwapDirection(*direction)),
+            );
+        }
+
+        // Super+1..9,0 --> view workspace 0..9, with Shift --> move the window there
+        let digits = [
+            keysym::XK_1,
+            keysym::XK_2,
+            keysym::XK_3,
+            keysym::XK_4,
+            keysym::XK_5,
+            keysym::XK_6,
+            keysym::XK_7,
+            keysym::XK_8,
+            keysym::XK_9,
+            keysym::XK_0,
+        ];
+        for (workspace, digit) in digits.iter().enumerate() {
+            key_bindings.insert(
+                KeyCombination::new(vec![Modifier::Super], (*digit).into()),
+                Action::Builtin(BuiltinCommand::ViewWorkspace(workspace)),
+            );
+            key_bindings.insert(
+                KeyCombination::new(vec![Modifier::Super, Modifier::Shift], (*digit).into()),
+                Action::Builtin(BuiltinCommand::MoveToWorkspace(workspace)),
+            );
+        }
 
-        let new = Self {
+        Self {
             gaps: 8,
             shift_by: 10,
+            border_width: 2,
+            border_color: Color(0x444444),
+            focused_border_color: Color(0x005577),
+            urgent_border_color: Color(0xff0000),
+            title_bars: false,
+            title_bar_height: 18,
+            title_font: "monospace:size=10".to_owned(),
+            title_color: Color(0xbbbbbb),
+            focused_title_color: Color(0xeeeeee),
+            watch: false,
+            layout: LayoutKind::Stack,
+            workspace_layouts: HashMap::new(),
+            layouts: vec![LayoutKind::Stack, LayoutKind::Monocle, LayoutKind::Grid],
+            master_count: 1,
+            workspace_master_counts: HashMap::new(),
+            split_ratio: 0.5,
+            min_client_size: 100,
+            tiled_size_hints: false,
             key_bindings,
+        }
+    }
+
+    /// The layout the given workspace starts with
+    pub fn layout_for(&self, workspace: usize) -> LayoutKind {
+        self.workspace_layouts
+            .get(&workspace)
+            .cloned()
+            .unwrap_or(self.layout)
+    }
+
+    /// How many clients share the master area when the given workspace starts
+    pub fn master_count_for(&self, workspace: usize) -> usize {
+        self.workspace_master_counts
+            .get(&workspace)
+            .cloned()
+            .unwrap_or(self.master_count)
+    }
+
+    /// The height of the title bars in px, 0 if they are disabled
+    pub fn title_height(&self) -> i32 {
+        if self.title_bars {
+            self.title_bar_height as i32
+        } else {
+            0
+        }
+    }
+
+    /// Finds the config file to use.
+    /// An explicitly passed path always wins, otherwise
+    /// $XDG_CONFIG_HOME/simple_wm and ~/.config/simple_wm are searched (in that order)
+    pub fn locate(explicit: Option<&Path>) -> Option<PathBuf> {
+        if let Some(path) = explicit {
+            return Some(path.to_owned());
+        }
+
+        let xdg = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from);
+        let home = env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"));
+
+        xdg.into_iter()
+            .chain(home)
+            .map(|dir| dir.join("simple_wm").join(CONFIG_FILE_NAME))
+            .find(|path| path.is_file())
+    }
+
+    /// Loads the config from the given file.
+    /// Fields which are missing in the file are taken from the built-in defaults,
+    /// values the layouts can't handle are clamped
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let content = fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+
+        let config: Self = serde_json::from_str(&content).map_err(|err| {
+            // serde_json appends the position itself, but we report it separately
+            let position = format!(" at line {} column {}", err.line(), err.column());
+            ConfigError::Parse {
+                path: path.to_owned(),
+                line: err.line(),
+                column: err.column(),
+                message: err.to_string().trim_end_matches(&position).to_owned(),
+            }
+        })?;
+        Ok(config.clamped())
+    }
+
+    /// Brings values which would give the layouts negative sizes into a sane range
+    fn clamped(mut self) -> Self {
+        self.split_ratio = self.split_ratio.clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO);
+        // the layouts work with i32
+        self.min_client_size = self.min_client_size.min(i32::MAX as usize);
+        self
+    }
+
+    /// Locates and loads the config; without any config file the built-in defaults are used
+    pub fn load(explicit: Option<&Path>) -> Result<Self, ConfigError> {
+        match Self::locate(explicit) {
+            Some(path) => Self::from_file(&path),
+            None => Ok(Self::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_ratio_is_clamped() {
+        let path = env::temp_dir().join(format!("simple_wm_config_{}.json", std::process::id()));
+        fs::write(&path, r#"{ "split_ratio": 1.5 }"#).unwrap();
+        assert_eq!(
+            Config::from_file(&path).unwrap().split_ratio,
+            MAX_SPLIT_RATIO
+        );
+        fs::write(&path, r#"{ "split_ratio": -0.5, "min_client_size": 50 }"#).unwrap();
+        let config = Config::from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(config.split_ratio, MIN_SPLIT_RATIO);
+        assert_eq!(config.min_client_size, 50);
+    }
+
+    #[test]
+    fn parse_errors_name_the_position() {
+        let path = env::temp_dir().join(format!("simple_wm_invalid_{}.json", std::process::id()));
+        fs::write(&path, "{\n    \"gaps\": 5,\n    \"border_width\" 2\n}\n").unwrap();
+        let err = match Config::from_file(&path) {
+            Err(err) => err,
+            Ok(_) => panic!("the config should be invalid"),
         };
-        new
+        fs::remove_file(&path).unwrap();
+        match &err {
+            ConfigError::Parse { line, column, .. } => assert_eq!((*line, *column), (3, 20)),
+            err => panic!("unexpected error {}", err),
+        }
+        let position = format!("{}:3:20: ", path.display());
+        assert!(err.to_string().contains(&position), "{}", err);
     }
 }
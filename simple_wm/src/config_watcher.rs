@@ -0,0 +1,98 @@
+use std::ffi::{CString, OsString};
+use std::io;
+use std::mem;
+use std::os::raw::c_int;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+/// Watches the config file via inotify.
+/// The directory is watched rather than the file itself, as most editors
+/// replace the file on save instead of writing into it
+pub struct ConfigWatcher {
+    fd: c_int,
+    file_name: OsString,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: &Path) -> io::Result<Self> {
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file"))?
+            .to_owned();
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let dir = CString::new(dir.as_os_str().as_bytes())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+        unsafe {
+            let fd = libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC);
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mask = libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO | libc::IN_CREATE;
+            if libc::inotify_add_watch(fd, dir.as_ptr(), mask) < 0 {
+                let err = io::Error::last_os_error();
+                libc::close(fd);
+                return Err(err);
+            }
+            Ok(Self { fd, file_name })
+        }
+    }
+
+    /// The file descriptor which becomes readable as soon as something happened
+    pub fn fd(&self) -> c_int {
+        self.fd
+    }
+
+    /// Drains all pending events and reports whether the config file was among them
+    pub fn config_changed(&self) -> bool {
+        let mut changed = false;
+        let mut buffer = [0u8; 4096];
+        let header_size = mem::size_of::<libc::inotify_event>();
+
+        loop {
+            let read = unsafe {
+                libc::read(
+                    self.fd,
+                    buffer.as_mut_ptr() as *mut libc::c_void,
+                    buffer.len(),
+                )
+            };
+            // nothing more to read (EAGAIN) or the fd is broken
+            if read <= 0 {
+                break;
+            }
+
+            let mut offset = 0;
+            while offset + header_size <= read as usize {
+                let event = unsafe {
+                    (buffer.as_ptr().add(offset) as *const libc::inotify_event).read_unaligned()
+                };
+                let name_start = offset + header_size;
+                let name_end = name_start + event.len as usize;
+                // the name is padded with NUL bytes
+                let name = &buffer[name_start..name_end];
+                let name_len = name
+                    .iter()
+                    .position(|byte| *byte == 0)
+                    .unwrap_or(name.len());
+
+                if &name[..name_len] == self.file_name.as_bytes() {
+                    changed = true;
+                }
+                offset = name_end;
+            }
+        }
+        changed
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
@@ -1,34 +1,67 @@
+use crate::backend::Backend;
 use crate::key_handler::*;
-use crate::layout::Layout;
 use crate::tag::Tag;
-use crate::window_system::WindowSystem;
+use crate::window_manager::{WindowManager, MAX_WORKSPACES};
 use std::process::*;
 use std::thread::spawn;
-use x11::xlib;
-use x11::xlib::Window;
 
 impl Action {
-    pub fn execute(
-        &mut self,
-        system: &WindowSystem,
-        shift_by: usize,
-        current: Option<Window>,
-        layout: &mut dyn Layout,
-        tag: &mut Tag,
-    ) {
+    pub fn execute<B: Backend>(&self, wm: &mut WindowManager<B>) {
         match self {
             // builtin action --> delegate!
             Action::Builtin(builtin) => {
+                let current = wm.current_window;
                 match builtin {
-                    BuiltinCommand::Close => unsafe {
+                    BuiltinCommand::Close => {
                         // if it is *some*, we know that it itsn't the root window
-                        // --> kill window
+                        // --> close window
                         if let Some(current) = current {
-                            xlib::XKillClient(system.display, current);
+                            wm.window_system.close(current);
                         }
-                    },
-                    BuiltinCommand::MoveLeft => layout.shift_left(tag, shift_by, system, current),
-                    BuiltinCommand::MoveRight => layout.shift_right(tag, shift_by, system, current),
+                    }
+                    BuiltinCommand::ForceKill => {
+                        if let Some(current) = current {
+                            wm.window_system.kill(current);
+                        }
+                    }
+                    BuiltinCommand::MoveLeft => wm.shift_master(-(wm.config.shift_by as i32)),
+                    BuiltinCommand::MoveRight => wm.shift_master(wm.config.shift_by as i32),
+                    BuiltinCommand::ReloadConfig => wm.reload_config(),
+                    BuiltinCommand::ViewWorkspace(index) => wm.view_workspace(*index),
+                    BuiltinCommand::ViewNextWorkspace => {
+                        wm.view_workspace((wm.current_workspace + 1) % MAX_WORKSPACES)
+                    }
+                    BuiltinCommand::ViewPreviousWorkspace => wm.view_workspace(
+                        (wm.current_workspace + MAX_WORKSPACES - 1) % MAX_WORKSPACES,
+                    ),
+                    BuiltinCommand::ViewLastWorkspace => wm.view_workspace(wm.last_workspace),
+                    BuiltinCommand::MoveToWorkspace(index) => wm.move_to_workspace(*index, false),
+                    BuiltinCommand::MoveToWorkspaceAndFollow(index) => {
+                        wm.move_to_workspace(*index, true)
+                    }
+                    BuiltinCommand::FocusNext => wm.focus_relative(true),
+                    BuiltinCommand::FocusPrevious => wm.focus_relative(false),
+                    BuiltinCommand::Zoom => wm.rearrange_current(Tag::zoom),
+                    BuiltinCommand::MoveUp => {
+                        wm.rearrange_current(|tag, window| tag.move_window(window, true))
+                    }
+                    BuiltinCommand::MoveDown => {
+                        wm.rearrange_current(|tag, window| tag.move_window(window, false))
+                    }
+                    BuiltinCommand::FocusDirection(direction) => wm.focus_direction(*direction),
+                    BuiltinCommand::SwapDirection(direction) => {
+                        let direction = *direction;
+                        wm.rearrange_current(|tag, window| {
+                            if let Some(neighbour) = tag.neighbour(window, direction) {
+                                tag.swap_windows(window, &neighbour);
+                            }
+                        })
+                    }
+                    BuiltinCommand::SetLayout(kind) => wm.set_layout(*kind),
+                    BuiltinCommand::CycleLayout => wm.cycle_layout(),
+                    BuiltinCommand::IncreaseMasterCount => wm.adjust_master_count(true),
+                    BuiltinCommand::DecreaseMasterCount => wm.adjust_master_count(false),
+                    BuiltinCommand::ToggleFloating => wm.toggle_floating(),
                 }
             }
             // custom command --> execute it!
@@ -1,15 +1,34 @@
 mod action;
 mod modifier;
+mod parse;
 
+use crate::layout::LayoutKind;
+use crate::models::Direction;
 use serde::{Deserialize, Serialize};
 use std::hash::Hash;
+use x11::xlib::KeySym;
 
-#[derive(Deserialize, Serialize, Hash, Eq, PartialEq, Debug)]
+/// A key together with its modifiers.
+/// In the config, it is written as a string like "Alt+Shift+q"
+#[derive(Deserialize, Serialize, Hash, Eq, PartialEq, Clone, Debug)]
+#[serde(try_from = "String", into = "String")]
 pub struct KeyCombination {
+    /// Always sorted & without duplicates, so that equal combinations hash equally
     pub modifiers: Vec<Modifier>,
     pub key: Key,
 }
 
+/// Everything that can go wrong when parsing a key combination
+#[derive(Debug, Eq, PartialEq)]
+pub enum KeyParseError {
+    /// There is nothing to parse at all
+    Empty,
+    /// The combination consists only of modifiers, e.g. "Super+"
+    MissingKey,
+    UnknownModifier(String),
+    UnknownKey(String),
+}
+
 #[derive(Deserialize, Serialize, Clone, Eq, PartialEq, Hash, Debug)]
 pub enum Action {
     Builtin(BuiltinCommand),
@@ -18,239 +37,71 @@ pub enum Action {
 
 #[derive(Deserialize, Serialize, Clone, Eq, PartialEq, Hash, Debug)]
 pub enum BuiltinCommand {
+    /// Asks the focused client to close its window (WM_DELETE_WINDOW),
+    /// killing it only if it doesn't support that
     Close,
+    /// Kills the focused client, including all of its other windows
+    ForceKill,
+    /// Shrinks the master area by `Config::shift_by` px
     MoveLeft,
+    /// Grows the master area by `Config::shift_by` px
     MoveRight,
+    /// Re-reads the config file; on errors, the old config stays active
+    ReloadConfig,
+    /// Shows the workspace with the given index (starting at 0)
+    ViewWorkspace(usize),
+    ViewNextWorkspace,
+    ViewPreviousWorkspace,
+    /// Goes back to the workspace which was visible before the current one
+    ViewLastWorkspace,
+    /// Moves the focused client to the given workspace, staying on the current one
+    MoveToWorkspace(usize),
+    /// Moves the focused client to the given workspace and shows that workspace
+    MoveToWorkspaceAndFollow(usize),
+    /// Focuses the next client in stacking order
+    FocusNext,
+    /// Focuses the previous client in stacking order
+    FocusPrevious,
+    /// Swaps the focused client with the master
+    Zoom,
+    /// Moves the focused client one position up the stack
+    MoveUp,
+    /// Moves the focused client one position down the stack
+    MoveDown,
+    /// Focuses the closest client in the given direction
+    FocusDirection(Direction),
+    /// Swaps the focused client with the closest client in the given direction
+    SwapDirection(Direction),
+    /// Arranges the current workspace with the given layout
+    SetLayout(LayoutKind),
+    /// Switches the current workspace to the next layout of `Config::layouts`
+    CycleLayout,
+    /// Puts one more client into the master area
+    IncreaseMasterCount,
+    /// Puts one client less into the master area
+    DecreaseMasterCount,
+    /// Takes the focused client out of the layout or puts it back in
+    ToggleFloating,
 }
 
-#[derive(Deserialize, Serialize, Clone, Copy, Eq, PartialEq, Hash, Debug)]
+#[derive(Deserialize, Serialize, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub enum Modifier {
     Alt = 0x08,
     // windows key
     Super = 0x40,
     // shift left
     Shift = 0x01,
+    Control = 0x04,
+    // usually NumLock, see `Modifier::numlock_mask`
+    Mod2 = 0x10,
+    Mod3 = 0x20,
+    // usually AltGr
+    Mod5 = 0x80,
 }
 
+/// Any X keysym.
+/// In the config, it is written by its X name (e.g. "Return", "XF86AudioMute"),
+/// optionally with the "XK_" prefix, or as a raw value like "0xff0d"
 #[derive(Deserialize, Serialize, Clone, Copy, Eq, PartialEq, Hash, Debug)]
-#[allow(non_camel_case_types)]
-pub enum Key {
-    // Numbers
-    XK_0 = 0x030,
-    XK_1 = 0x031,
-    XK_2 = 0x032,
-    XK_3 = 0x033,
-    XK_4 = 0x034,
-    XK_5 = 0x035,
-    XK_6 = 0x036,
-    XK_7 = 0x037,
-    XK_8 = 0x038,
-    XK_9 = 0x039,
-
-    // "Normal" keys
-    XK_a = 0x061,
-    XK_b = 0x062,
-    XK_c = 0x063,
-    XK_d = 0x064,
-    XK_e = 0x065,
-    XK_f = 0x066,
-    XK_g = 0x067,
-    XK_h = 0x068,
-    XK_i = 0x069,
-    XK_j = 0x06a,
-    XK_k = 0x06b,
-    XK_l = 0x06c,
-    XK_m = 0x06d,
-    XK_n = 0x06e,
-    XK_o = 0x06f,
-    XK_p = 0x070,
-    XK_q = 0x071,
-    XK_r = 0x072,
-    XK_s = 0x073,
-    XK_t = 0x074,
-    XK_u = 0x075,
-    XK_v = 0x076,
-    XK_w = 0x077,
-    XK_x = 0x078,
-    XK_y = 0x079,
-    XK_z = 0x07a,
-
-    // XF86 Keys
-    XF86XK_ModeLock = 0x1008FF01,
-    XF86XK_MonBrightnessUp = 0x1008FF02,
-    XF86XK_MonBrightnessDown = 0x1008FF03,
-    XF86XK_KbdLightOnOff = 0x1008FF04,
-    XF86XK_KbdBrightnessUp = 0x1008FF05,
-    XF86XK_KbdBrightnessDown = 0x1008FF06,
-    XF86XK_Standby = 0x1008FF10,
-    XF86XK_AudioLowerVolume = 0x1008FF11,
-    XF86XK_AudioMute = 0x1008FF12,
-    XF86XK_AudioRaiseVolume = 0x1008FF13,
-    XF86XK_AudioPlay = 0x1008FF14,
-    XF86XK_AudioStop = 0x1008FF15,
-    XF86XK_AudioPrev = 0x1008FF16,
-    XF86XK_AudioNext = 0x1008FF17,
-    XF86XK_HomePage = 0x1008FF18,
-    XF86XK_Mail = 0x1008FF19,
-    XF86XK_Start = 0x1008FF1A,
-    XF86XK_Search = 0x1008FF1B,
-    XF86XK_AudioRecord = 0x1008FF1C,
-    XF86XK_Calculator = 0x1008FF1D,
-    XF86XK_Memo = 0x1008FF1E,
-    XF86XK_ToDoList = 0x1008FF1F,
-    XF86XK_Calendar = 0x1008FF20,
-    XF86XK_PowerDown = 0x1008FF21,
-    XF86XK_ContrastAdjust = 0x1008FF22,
-    XF86XK_RockerUp = 0x1008FF23,
-    XF86XK_RockerDown = 0x1008FF24,
-    XF86XK_RockerEnter = 0x1008FF25,
-    XF86XK_Back = 0x1008FF26,
-    XF86XK_Forward = 0x1008FF27,
-    XF86XK_Stop = 0x1008FF28,
-    XF86XK_Refresh = 0x1008FF29,
-    XF86XK_PowerOff = 0x1008FF2A,
-    XF86XK_WakeUp = 0x1008FF2B,
-    XF86XK_Eject = 0x1008FF2C,
-    XF86XK_ScreenSaver = 0x1008FF2D,
-    XF86XK_WWW = 0x1008FF2E,
-    XF86XK_Sleep = 0x1008FF2F,
-    XF86XK_Favorites = 0x1008FF30,
-    XF86XK_AudioPause = 0x1008FF31,
-    XF86XK_AudioMedia = 0x1008FF32,
-    XF86XK_MyComputer = 0x1008FF33,
-    XF86XK_VendorHome = 0x1008FF34,
-    XF86XK_LightBulb = 0x1008FF35,
-    XF86XK_Shop = 0x1008FF36,
-    XF86XK_History = 0x1008FF37,
-    XF86XK_OpenURL = 0x1008FF38,
-    XF86XK_AddFavorite = 0x1008FF39,
-    XF86XK_HotLinks = 0x1008FF3A,
-    XF86XK_BrightnessAdjust = 0x1008FF3B,
-    XF86XK_Finance = 0x1008FF3C,
-    XF86XK_Community = 0x1008FF3D,
-    XF86XK_AudioRewind = 0x1008FF3E,
-    XF86XK_BackForward = 0x1008FF3F,
-    XF86XK_Launch0 = 0x1008FF40,
-    XF86XK_Launch1 = 0x1008FF41,
-    XF86XK_Launch2 = 0x1008FF42,
-    XF86XK_Launch3 = 0x1008FF43,
-    XF86XK_Launch4 = 0x1008FF44,
-    XF86XK_Launch5 = 0x1008FF45,
-    XF86XK_Launch6 = 0x1008FF46,
-    XF86XK_Launch7 = 0x1008FF47,
-    XF86XK_Launch8 = 0x1008FF48,
-    XF86XK_Launch9 = 0x1008FF49,
-    XF86XK_LaunchA = 0x1008FF4A,
-    XF86XK_LaunchB = 0x1008FF4B,
-    XF86XK_LaunchC = 0x1008FF4C,
-    XF86XK_LaunchD = 0x1008FF4D,
-    XF86XK_LaunchE = 0x1008FF4E,
-    XF86XK_LaunchF = 0x1008FF4F,
-    XF86XK_ApplicationLeft = 0x1008FF50,
-    XF86XK_ApplicationRight = 0x1008FF51,
-    XF86XK_Book = 0x1008FF52,
-    XF86XK_CD = 0x1008FF53,
-    XF86XK_Calculater = 0x1008FF54,
-    XF86XK_Clear = 0x1008FF55,
-    XF86XK_Close = 0x1008FF56,
-    XF86XK_Copy = 0x1008FF57,
-    XF86XK_Cut = 0x1008FF58,
-    XF86XK_Display = 0x1008FF59,
-    XF86XK_DOS = 0x1008FF5A,
-    XF86XK_Documents = 0x1008FF5B,
-    XF86XK_Excel = 0x1008FF5C,
-    XF86XK_Explorer = 0x1008FF5D,
-    XF86XK_Game = 0x1008FF5E,
-    XF86XK_Go = 0x1008FF5F,
-    XF86XK_iTouch = 0x1008FF60,
-    XF86XK_LogOff = 0x1008FF61,
-    XF86XK_Market = 0x1008FF62,
-    XF86XK_Meeting = 0x1008FF63,
-    XF86XK_MenuKB = 0x1008FF65,
-    XF86XK_MenuPB = 0x1008FF66,
-    XF86XK_MySites = 0x1008FF67,
-    XF86XK_New = 0x1008FF68,
-    XF86XK_News = 0x1008FF69,
-    XF86XK_OfficeHome = 0x1008FF6A,
-    XF86XK_Open = 0x1008FF6B,
-    XF86XK_Option = 0x1008FF6C,
-    XF86XK_Paste = 0x1008FF6D,
-    XF86XK_Phone = 0x1008FF6E,
-    XF86XK_Q = 0x1008FF70,
-    XF86XK_Reply = 0x1008FF72,
-    XF86XK_Reload = 0x1008FF73,
-    XF86XK_RotateWindows = 0x1008FF74,
-    XF86XK_RotationPB = 0x1008FF75,
-    XF86XK_RotationKB = 0x1008FF76,
-    XF86XK_Save = 0x1008FF77,
-    XF86XK_ScrollUp = 0x1008FF78,
-    XF86XK_ScrollDown = 0x1008FF79,
-    XF86XK_ScrollClick = 0x1008FF7A,
-    XF86XK_Send = 0x1008FF7B,
-    XF86XK_Spell = 0x1008FF7C,
-    XF86XK_SplitScreen = 0x1008FF7D,
-    XF86XK_Support = 0x1008FF7E,
-    XF86XK_TaskPane = 0x1008FF7F,
-    XF86XK_Terminal = 0x1008FF80,
-    XF86XK_Tools = 0x1008FF81,
-    XF86XK_Travel = 0x1008FF82,
-    XF86XK_UserPB = 0x1008FF84,
-    XF86XK_User1KB = 0x1008FF85,
-    XF86XK_User2KB = 0x1008FF86,
-    XF86XK_Video = 0x1008FF87,
-    XF86XK_WheelButton = 0x1008FF88,
-    XF86XK_Word = 0x1008FF89,
-    XF86XK_Xfer = 0x1008FF8A,
-    XF86XK_ZoomIn = 0x1008FF8B,
-    XF86XK_ZoomOut = 0x1008FF8C,
-    XF86XK_Away = 0x1008FF8D,
-    XF86XK_Messenger = 0x1008FF8E,
-    XF86XK_WebCam = 0x1008FF8F,
-    XF86XK_MailForward = 0x1008FF90,
-    XF86XK_Pictures = 0x1008FF91,
-    XF86XK_Music = 0x1008FF92,
-    XF86XK_Battery = 0x1008FF93,
-    XF86XK_Bluetooth = 0x1008FF94,
-    XF86XK_WLAN = 0x1008FF95,
-    XF86XK_UWB = 0x1008FF96,
-    XF86XK_AudioForward = 0x1008FF97,
-    XF86XK_AudioRepeat = 0x1008FF98,
-    XF86XK_AudioRandomPlay = 0x1008FF99,
-    XF86XK_Subtitle = 0x1008FF9A,
-    XF86XK_AudioCycleTrack = 0x1008FF9B,
-    XF86XK_CycleAngle = 0x1008FF9C,
-    XF86XK_FrameBack = 0x1008FF9D,
-    XF86XK_FrameForward = 0x1008FF9E,
-    XF86XK_Time = 0x1008FF9F,
-    XF86XK_Select = 0x1008FFA0,
-    XF86XK_View = 0x1008FFA1,
-    XF86XK_TopMenu = 0x1008FFA2,
-    XF86XK_Red = 0x1008FFA3,
-    XF86XK_Green = 0x1008FFA4,
-    XF86XK_Yellow = 0x1008FFA5,
-    XF86XK_Blue = 0x1008FFA6,
-    XF86XK_Suspend = 0x1008FFA7,
-    XF86XK_Hibernate = 0x1008FFA8,
-    XF86XK_TouchpadToggle = 0x1008FFA9,
-    XF86XK_TouchpadOn = 0x1008FFB0,
-    XF86XK_TouchpadOff = 0x1008FFB1,
-    XF86XK_AudioMicMute = 0x1008FFB2,
-    XF86XK_Switch_VT_1 = 0x1008FE01,
-    XF86XK_Switch_VT_2 = 0x1008FE02,
-    XF86XK_Switch_VT_3 = 0x1008FE03,
-    XF86XK_Switch_VT_4 = 0x1008FE04,
-    XF86XK_Switch_VT_5 = 0x1008FE05,
-    XF86XK_Switch_VT_6 = 0x1008FE06,
-    XF86XK_Switch_VT_7 = 0x1008FE07,
-    XF86XK_Switch_VT_8 = 0x1008FE08,
-    XF86XK_Switch_VT_9 = 0x1008FE09,
-    XF86XK_Switch_VT_10 = 0x1008FE0A,
-    XF86XK_Switch_VT_11 = 0x1008FE0B,
-    XF86XK_Switch_VT_12 = 0x1008FE0C,
-    XF86XK_Ungrab = 0x1008FE20,
-    XF86XK_ClearGrab = 0x1008FE21,
-    XF86XK_Next_VMode = 0x1008FE22,
-    XF86XK_Prev_VMode = 0x1008FE23,
-    XF86XK_LogWindowTree = 0x1008FE24,
-    XF86XK_LogGrabInfo = 0x1008FE25,
-}
+#[serde(try_from = "String", into = "String")]
+pub struct Key(pub KeySym);
@@ -1,26 +1,76 @@
 use crate::key_handler::*;
 
+use std::slice;
+use x11::keysym;
 use x11::xlib;
+use x11::xlib::Display;
+
 impl KeyCombination {
+    pub fn new(mut modifiers: Vec<Modifier>, key: Key) -> Self {
+        modifiers.sort();
+        modifiers.dedup();
+        Self { modifiers, key }
+    }
+
     pub fn get_mask(&self) -> u32 {
         self.modifiers
             .iter()
             .fold(0, |acc, curr| *curr as u32 | acc)
     }
+
+    /// All the masks which have to be grabbed so that the combination
+    /// also works while CapsLock and/or NumLock are active
+    pub fn get_masks(&self, numlock_mask: u32) -> [u32; 4] {
+        let mask = self.get_mask();
+        [
+            mask,
+            mask | xlib::LockMask,
+            mask | numlock_mask,
+            mask | xlib::LockMask | numlock_mask,
+        ]
+    }
 }
 
 impl Modifier {
-    pub fn from_event(evt: &xlib::XKeyEvent) -> Vec<Self> {
-        let mut modifiers = vec![];
-        if evt.state & Modifier::Alt as u32 != 0 {
-            modifiers.push(Modifier::Alt);
-        }
-        if evt.state & Modifier::Shift as u32 != 0 {
-            modifiers.push(Modifier::Shift);
-        }
-        if evt.state & Modifier::Super as u32 != 0 {
-            modifiers.push(Modifier::Super);
+    const ALL: [Modifier; 7] = [
+        Modifier::Shift,
+        Modifier::Control,
+        Modifier::Alt,
+        Modifier::Mod2,
+        Modifier::Mod3,
+        Modifier::Super,
+        Modifier::Mod5,
+    ];
+
+    /// The modifiers of the event, without CapsLock and NumLock
+    pub fn from_event(evt: &xlib::XKeyEvent, numlock_mask: u32) -> Vec<Self> {
+        let state = evt.state & !(xlib::LockMask | numlock_mask);
+        Modifier::ALL
+            .iter()
+            .filter(|modifier| state & **modifier as u32 != 0)
+            .cloned()
+            .collect()
+    }
+
+    /// Finds the modifier bit NumLock is currently mapped to (0 if there is none)
+    pub fn numlock_mask(display: *mut Display) -> u32 {
+        unsafe {
+            let numlock = xlib::XKeysymToKeycode(display, keysym::XK_Num_Lock.into());
+            let map = xlib::XGetModifierMapping(display);
+            if map.is_null() {
+                return 0;
+            }
+
+            // 8 modifiers with up to max_keypermod keycodes each
+            let per_modifier = (*map).max_keypermod as usize;
+            let keycodes = slice::from_raw_parts((*map).modifiermap, 8 * per_modifier);
+            let mask = keycodes
+                .chunks(per_modifier.max(1))
+                .position(|codes| numlock != 0 && codes.contains(&numlock))
+                .map_or(0, |index| 1 << index);
+
+            xlib::XFreeModifiermap(map);
+            mask
         }
-        modifiers
     }
 }
@@ -0,0 +1,229 @@
+use crate::key_handler::*;
+use std::convert::TryFrom;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::os::raw::c_uint;
+use std::str::FromStr;
+use x11::xlib;
+use x11::xlib::KeySym;
+
+impl fmt::Display for KeyParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyParseError::Empty => write!(f, "empty key combination"),
+            KeyParseError::MissingKey => write!(f, "key combination without a key"),
+            KeyParseError::UnknownModifier(name) => write!(
+                f,
+                "unknown modifier '{}' (expected one of Alt, Super, Shift, Control, Mod2, Mod3, Mod5)",
+                name
+            ),
+            KeyParseError::UnknownKey(name) => write!(f, "unknown key '{}'", name),
+        }
+    }
+}
+
+impl std::error::Error for KeyParseError {}
+
+impl FromStr for Modifier {
+    type Err = KeyParseError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "alt" | "mod1" => Ok(Modifier::Alt),
+            "super" | "mod4" | "win" => Ok(Modifier::Super),
+            "shift" => Ok(Modifier::Shift),
+            "control" | "ctrl" => Ok(Modifier::Control),
+            "mod2" => Ok(Modifier::Mod2),
+            "mod3" => Ok(Modifier::Mod3),
+            "mod5" => Ok(Modifier::Mod5),
+            _ => Err(KeyParseError::UnknownModifier(name.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for Modifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl FromStr for Key {
+    type Err = KeyParseError;
+
+    /// Resolves the name via the X keysym table.
+    /// The "XK_" prefix of the old key names is accepted as well
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let unknown = || KeyParseError::UnknownKey(name.to_owned());
+
+        if let Some(hex) = name.strip_prefix("0x").or_else(|| name.strip_prefix("0X")) {
+            return KeySym::from_str_radix(hex, 16)
+                .map(Key)
+                .map_err(|_| unknown());
+        }
+
+        let name = match name.strip_prefix("XF86XK_") {
+            Some(rest) => format!("XF86{}", rest),
+            None => name.strip_prefix("XK_").unwrap_or(name).to_owned(),
+        };
+        let c_name = CString::new(name).map_err(|_| unknown())?;
+        match unsafe { xlib::XStringToKeysym(c_name.as_ptr()) } {
+            0 => Err(unknown()),
+            keysym => Ok(Key(keysym)),
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    /// Writes the X name of the keysym, which is also accepted by `from_str`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = unsafe { xlib::XKeysymToString(self.0) };
+        if name.is_null() {
+            write!(f, "{:#x}", self.0)
+        } else {
+            f.write_str(&unsafe { CStr::from_ptr(name) }.to_string_lossy())
+        }
+    }
+}
+
+impl From<c_uint> for Key {
+    fn from(keysym: c_uint) -> Self {
+        Key(keysym.into())
+    }
+}
+
+impl TryFrom<String> for Key {
+    type Error = KeyParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Key> for String {
+    fn from(key: Key) -> Self {
+        key.to_string()
+    }
+}
+
+impl FromStr for KeyCombination {
+    type Err = KeyParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(KeyParseError::Empty);
+        }
+
+        // the last part is the key, everything before it are modifiers
+        let mut parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        let key = match parts.pop() {
+            Some(key) if !key.is_empty() => key.parse()?,
+            _ => return Err(KeyParseError::MissingKey),
+        };
+        let modifiers = parts
+            .into_iter()
+            .map(str::parse)
+            .collect::<Result<Vec<Modifier>, _>>()?;
+
+        Ok(KeyCombination::new(modifiers, key))
+    }
+}
+
+impl fmt::Display for KeyCombination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for modifier in self.modifiers.iter() {
+            write!(f, "{}+", modifier)?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+impl TryFrom<String> for KeyCombination {
+    type Error = KeyParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<KeyCombination> for String {
+    fn from(combination: KeyCombination) -> Self {
+        combination.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use x11::keysym;
+
+    #[test]
+    fn keys_are_parsed() {
+        let key = |name: &str| name.parse::<Key>();
+        assert_eq!(key("Return"), Ok(Key(keysym::XK_Return.into())));
+        assert_eq!(key("q"), Ok(Key(keysym::XK_q.into())));
+        assert_eq!(key("XK_q"), Ok(Key(keysym::XK_q.into())));
+        assert_eq!(key("XF86AudioMute"), Ok(Key(0x1008ff12)));
+        assert_eq!(key("XF86XK_AudioMute"), Ok(Key(0x1008ff12)));
+        assert_eq!(key("0x1008ff12"), Ok(Key(0x1008ff12)));
+        assert_eq!(key("0X71"), Ok(Key(keysym::XK_q.into())));
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        for name in &["XK_XK_q", "NoSuchKey", "0xnope", "XF86XK_"] {
+            assert_eq!(
+                name.parse::<Key>(),
+                Err(KeyParseError::UnknownKey(name.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn combinations_are_parsed() {
+        let combination: KeyCombination = " Super + shift+Return ".parse().unwrap();
+        assert_eq!(
+            combination,
+            KeyCombination::new(
+                vec![Modifier::Super, Modifier::Shift],
+                keysym::XK_Return.into()
+            )
+        );
+        assert_eq!(
+            "q".parse(),
+            Ok(KeyCombination::new(vec![], keysym::XK_q.into()))
+        );
+    }
+
+    #[test]
+    fn modifiers_are_sorted_and_deduplicated() {
+        let combination: KeyCombination = "alt+shift+mod1+Shift+q".parse().unwrap();
+        assert_eq!(combination, "Shift+Alt+q".parse().unwrap());
+        assert_eq!(combination.modifiers, vec![Modifier::Shift, Modifier::Alt]);
+    }
+
+    #[test]
+    fn combinations_round_trip() {
+        let combination: KeyCombination = "ctrl+win+XF86XK_AudioMute".parse().unwrap();
+        assert_eq!(combination.to_string(), "Control+Super+XF86AudioMute");
+        assert_eq!(combination.to_string().parse(), Ok(combination.clone()));
+
+        let json = serde_json::to_string(&combination).unwrap();
+        assert_eq!(json, r#""Control+Super+XF86AudioMute""#);
+        let parsed: KeyCombination = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, combination);
+    }
+
+    #[test]
+    fn malformed_combinations_are_rejected() {
+        let parse = |s: &str| s.parse::<KeyCombination>();
+        assert_eq!(parse(""), Err(KeyParseError::Empty));
+        assert_eq!(parse("  "), Err(KeyParseError::Empty));
+        assert_eq!(parse("Super+"), Err(KeyParseError::MissingKey));
+        assert_eq!(
+            parse("Hyper+q"),
+            Err(KeyParseError::UnknownModifier("Hyper".to_owned()))
+        );
+        assert!(serde_json::from_str::<KeyCombination>(r#""Super+""#).is_err());
+    }
+}
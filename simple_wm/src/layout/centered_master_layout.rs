@@ -0,0 +1,104 @@
+use crate::layout::{column, master_and_stack, split_clients, Layout, LayoutKind, LayoutParams};
+use crate::models::Rect;
+
+/// The master area sits in the middle of the screen, the other clients
+/// alternate between the columns to the right and to the left of it.
+/// With a single stack client, it behaves like the stack layout
+pub struct CenteredMasterLayout {}
+
+impl CenteredMasterLayout {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Layout for CenteredMasterLayout {
+    fn arrange(&self, area: Rect, count: usize, params: &LayoutParams) -> Vec<Rect> {
+        let gaps = params.gaps;
+        let inner = area.shrink(gaps);
+        let (masters, stack) = split_clients(count, params.master_count);
+        // one kind of client or a single stack client --> like the stack layout
+        if masters == 0 || stack <= 1 {
+            return master_and_stack(inner, masters, stack, params);
+        }
+
+        // a gap on either side of the master
+        let usable_width = inner.width - 2 * gaps;
+        let master_width = (usable_width as f32 * params.master_ratio) as i32;
+        let left_width = (usable_width - master_width) / 2;
+        let right_width = usable_width - master_width - left_width;
+
+        let master_x = inner.x + left_width + gaps;
+        let right_x = master_x + master_width + gaps;
+        let master_area = Rect::new(master_x, inner.y, master_width, inner.height);
+        let left_area = Rect::new(inner.x, inner.y, left_width, inner.height);
+        let right_area = Rect::new(right_x, inner.y, right_width, inner.height);
+
+        let mut rects = column(master_area, masters, gaps);
+        let right = column(right_area, stack.div_ceil(2), gaps);
+        let left = column(left_area, stack / 2, gaps);
+        for index in 0..stack {
+            let side = if index % 2 == 0 { &right } else { &left };
+            rects.push(side[index / 2]);
+        }
+        rects
+    }
+
+    fn symbol(&self, _count: usize) -> String {
+        "|M|".to_string()
+    }
+
+    fn kind(&self) -> LayoutKind {
+        LayoutKind::CenteredMaster
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arrange(count: usize, master_count: usize) -> Vec<Rect> {
+        let params = LayoutParams {
+            gaps: 10,
+            master_count,
+            ..LayoutParams::default()
+        };
+        CenteredMasterLayout::new().arrange(Rect::new(0, 0, 1000, 800), count, &params)
+    }
+
+    #[test]
+    fn master_in_the_middle() {
+        assert_eq!(
+            arrange(4, 1),
+            vec![
+                Rect::new(260, 10, 480, 780),
+                Rect::new(750, 10, 240, 385),
+                Rect::new(10, 10, 240, 780),
+                Rect::new(750, 405, 240, 385),
+            ]
+        );
+    }
+
+    #[test]
+    fn single_stack_client_on_the_right() {
+        assert_eq!(
+            arrange(2, 1),
+            vec![Rect::new(10, 10, 485, 780), Rect::new(505, 10, 485, 780)]
+        );
+    }
+
+    #[test]
+    fn multiple_masters() {
+        let rects = arrange(4, 2);
+        assert_eq!(rects[0], Rect::new(260, 10, 480, 385));
+        assert_eq!(rects[1], Rect::new(260, 405, 480, 385));
+        assert_eq!(rects[2].x, 750);
+        assert_eq!(rects[3].x, 10);
+
+        // no stack left --> the masters share the screen
+        assert_eq!(
+            arrange(2, 3),
+            vec![Rect::new(10, 10, 980, 385), Rect::new(10, 405, 980, 385)]
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use crate::layout::{Layout, LayoutKind, LayoutParams};
+use crate::models::Rect;
+
+/// Every client splits the remaining area with the next one, alternating between
+/// side by side and top/bottom splits.
+/// With `spiral`, the remaining area rotates clockwise around the screen,
+/// otherwise it always dwindles towards the bottom right.
+/// Once a split would leave a client smaller than `LayoutParams::min_client_size`,
+/// all remaining clients are stacked on top of each other in the remaining area
+pub struct FibonacciLayout {
+    spiral: bool,
+}
+
+impl FibonacciLayout {
+    pub fn spiral() -> Self {
+        Self { spiral: true }
+    }
+
+    pub fn dwindle() -> Self {
+        Self { spiral: false }
+    }
+
+    /// Splits the area into the left & right (or top & bottom) part,
+    /// the first one taking up `ratio` of it
+    fn split_area(area: Rect, side_by_side: bool, ratio: f32, gaps: i32) -> (Rect, Rect) {
+        if side_by_side {
+            let width = ((area.width - gaps) as f32 * ratio) as i32;
+            let first = Rect::new(area.x, area.y, width, area.height);
+            let second = Rect::new(
+                area.x + width + gaps,
+                area.y,
+                area.width - width - gaps,
+                area.height,
+            );
+            (first, second)
+        } else {
+            let height = ((area.height - gaps) as f32 * ratio) as i32;
+            let first = Rect::new(area.x, area.y, area.width, height);
+            let second = Rect::new(
+                area.x,
+                area.y + height + gaps,
+                area.width,
+                area.height - height - gaps,
+            );
+            (first, second)
+        }
+    }
+}
+
+impl Layout for FibonacciLayout {
+    fn arrange(&self, area: Rect, count: usize, params: &LayoutParams) -> Vec<Rect> {
+        let mut rects = Vec::with_capacity(count);
+        let mut remaining = area.shrink(params.gaps);
+
+        for index in 0..count {
+            // the last client gets whatever is left
+            if index + 1 == count {
+                rects.push(remaining);
+                break;
+            }
+
+            let side_by_side = index % 2 == 0;
+            // the spiral turns around: from the 3rd split on, every other client takes the far side
+            let client_first = !self.spiral || index % 4 < 2;
+            let ratio = if client_first {
+                params.split_ratio
+            } else {
+                1.0 - params.split_ratio
+            };
+            let (first, second) = Self::split_area(remaining, side_by_side, ratio, params.gaps);
+            let (client, rest) = if client_first {
+                (first, second)
+            } else {
+                (second, first)
+            };
+
+            let too_small = |rect: Rect| {
+                rect.width < params.min_client_size || rect.height < params.min_client_size
+            };
+            if too_small(client) || too_small(rest) {
+                // stack the remaining clients
+                rects.resize(count, remaining);
+                break;
+            }
+            rects.push(client);
+            remaining = rest;
+        }
+        rects
+    }
+
+    fn symbol(&self, _count: usize) -> String {
+        if self.spiral {
+            "(@)".to_string()
+        } else {
+            "[\\]".to_string()
+        }
+    }
+
+    fn kind(&self) -> LayoutKind {
+        if self.spiral {
+            LayoutKind::Spiral
+        } else {
+            LayoutKind::Dwindle
+        }
+    }
+
+    /// Clients may be stacked on top of each other
+    fn raise_focused(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: Rect = Rect {
+        x: 0,
+        y: 0,
+        width: 1000,
+        height: 800,
+    };
+
+    fn params() -> LayoutParams {
+        LayoutParams {
+            gaps: 10,
+            ..LayoutParams::default()
+        }
+    }
+
+    #[test]
+    fn dwindle_shrinks_towards_the_bottom_right() {
+        assert_eq!(
+            FibonacciLayout::dwindle().arrange(SCREEN, 4, &params()),
+            vec![
+                Rect::new(10, 10, 485, 780),
+                Rect::new(505, 10, 485, 385),
+                Rect::new(505, 405, 237, 385),
+                Rect::new(752, 405, 238, 385),
+            ]
+        );
+    }
+
+    #[test]
+    fn spiral_turns_around() {
+        assert_eq!(
+            FibonacciLayout::spiral().arrange(SCREEN, 5, &params()),
+            vec![
+                Rect::new(10, 10, 485, 780),
+                Rect::new(505, 10, 485, 385),
+                Rect::new(752, 405, 238, 385),
+                Rect::new(505, 602, 237, 188),
+                Rect::new(505, 405, 237, 187),
+            ]
+        );
+    }
+
+    #[test]
+    fn split_ratio() {
+        let params = LayoutParams {
+            split_ratio: 0.75,
+            ..params()
+        };
+        let rects = FibonacciLayout::dwindle().arrange(SCREEN, 2, &params);
+        assert_eq!(
+            rects,
+            vec![Rect::new(10, 10, 727, 780), Rect::new(747, 10, 243, 780)]
+        );
+    }
+
+    #[test]
+    fn small_clients_are_stacked() {
+        let params = LayoutParams {
+            min_client_size: 400,
+            ..params()
+        };
+        let rects = FibonacciLayout::dwindle().arrange(SCREEN, 4, &params);
+        let rest = Rect::new(505, 10, 485, 780);
+        assert_eq!(rects, vec![Rect::new(10, 10, 485, 780), rest, rest, rest]);
+    }
+}
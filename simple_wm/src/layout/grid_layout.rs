@@ -0,0 +1,100 @@
+use crate::layout::{split, Layout, LayoutKind, LayoutParams};
+use crate::models::Rect;
+
+/// The clients are arranged in rows & columns, as close to a square as possible.
+/// If the last row isn't full, its clients grow to fill it
+pub struct GridLayout {}
+
+impl GridLayout {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Layout for GridLayout {
+    fn arrange(&self, area: Rect, count: usize, params: &LayoutParams) -> Vec<Rect> {
+        if count == 0 {
+            return vec![];
+        }
+        let gaps = params.gaps;
+        let inner = area.shrink(gaps);
+
+        let columns = (count as f32).sqrt().ceil() as usize;
+        let rows = count.div_ceil(columns);
+
+        let mut rects = Vec::with_capacity(count);
+        for (row, (y, height)) in split(inner.y, inner.height, rows, gaps)
+            .into_iter()
+            .enumerate()
+        {
+            let in_row = columns.min(count - row * columns);
+            for (x, width) in split(inner.x, inner.width, in_row, gaps) {
+                rects.push(Rect::new(x, y, width, height));
+            }
+        }
+        rects
+    }
+
+    fn symbol(&self, _count: usize) -> String {
+        "###".to_string()
+    }
+
+    fn kind(&self) -> LayoutKind {
+        LayoutKind::Grid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arrange(count: usize) -> Vec<Rect> {
+        let params = LayoutParams {
+            gaps: 10,
+            ..LayoutParams::default()
+        };
+        GridLayout::new().arrange(Rect::new(0, 0, 1000, 800), count, &params)
+    }
+
+    #[test]
+    fn near_square_grid() {
+        assert_eq!(arrange(1), vec![Rect::new(10, 10, 980, 780)]);
+        assert_eq!(
+            arrange(4),
+            vec![
+                Rect::new(10, 10, 485, 385),
+                Rect::new(505, 10, 485, 385),
+                Rect::new(10, 405, 485, 385),
+                Rect::new(505, 405, 485, 385),
+            ]
+        );
+    }
+
+    #[test]
+    fn last_row_is_filled() {
+        let rects = arrange(5);
+        assert_eq!(rects.len(), 5);
+        // 3 columns in the first row, 2 wider ones in the second
+        assert_eq!(rects[0], Rect::new(10, 10, 320, 385));
+        assert_eq!(rects[3], Rect::new(10, 405, 485, 385));
+        assert_eq!(rects[4], Rect::new(505, 405, 485, 385));
+    }
+
+    #[test]
+    fn no_pixels_left_at_the_edges() {
+        // 7 clients on an awkward size: every row & column must end at the inner edge
+        let area = Rect::new(0, 0, 1001, 767);
+        let params = LayoutParams {
+            gaps: 7,
+            ..LayoutParams::default()
+        };
+        let rects = GridLayout::new().arrange(area, 7, &params);
+        let right = rects.iter().map(|r| r.x + r.width).max();
+        let bottom = rects.iter().map(|r| r.y + r.height).max();
+        assert_eq!(right, Some(1001 - 7));
+        assert_eq!(bottom, Some(767 - 7));
+        for row in [&rects[0..3], &rects[3..6]].iter() {
+            assert_eq!(row.last().map(|r| r.x + r.width), Some(1001 - 7));
+        }
+    }
+}
@@ -1,24 +1,183 @@
+pub mod centered_master_layout;
+pub mod fibonacci_layout;
+pub mod grid_layout;
+pub mod monocle_layout;
 pub mod stack_layout;
+pub mod three_column_layout;
 
-use crate::config::Config;
-use crate::tag::Tag;
-use crate::window_system::WindowSystem;
-use x11::xlib::Window;
+use crate::models::Rect;
+use centered_master_layout::CenteredMasterLayout;
+use fibonacci_layout::FibonacciLayout;
+use grid_layout::GridLayout;
+use monocle_layout::MonocleLayout;
+use serde::{Deserialize, Serialize};
+use stack_layout::StackLayout;
+use three_column_layout::ThreeColumnLayout;
+
+/// Neither the master nor the stack column may become narrower than this (in px)
+pub const MIN_COLUMN_WIDTH: i32 = 50;
+
+/// Everything besides the screen area and the number of clients a layout depends on
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct LayoutParams {
+    /// The gaps in px, around and between clients
+    pub gaps: i32,
+    /// How much of the usable width the master area takes up
+    pub master_ratio: f32,
+    /// How many clients share the master area
+    pub master_count: usize,
+    /// Which part of the remaining area the spiral & dwindle layouts give to each client
+    pub split_ratio: f32,
+    /// Clients aren't split any further once they would get smaller than this (in px)
+    pub min_client_size: i32,
+}
+
+impl Default for LayoutParams {
+    fn default() -> Self {
+        Self {
+            gaps: 0,
+            master_ratio: 0.5,
+            master_count: 1,
+            split_ratio: 0.5,
+            min_client_size: 0,
+        }
+    }
+}
 
 pub trait Layout {
-    fn resize(&mut self, tag: &mut Tag, config: &Config, system: &WindowSystem);
-    fn shift_left(
-        &mut self,
-        tag: &mut Tag,
-        shift_by: usize,
-        system: &WindowSystem,
-        current: Option<Window>,
-    );
-    fn shift_right(
-        &mut self,
-        tag: &mut Tag,
-        shift_by: usize,
-        system: &WindowSystem,
-        current: Option<Window>,
+    /// The geometry of `count` clients within `area`, in stacking order (master first).
+    /// Layouts only compute geometry, the window manager applies it
+    fn arrange(&self, area: Rect, count: usize, params: &LayoutParams) -> Vec<Rect>;
+    /// A short indicator of the layout for status bars, e.g. "[]="
+    fn symbol(&self, count: usize) -> String;
+    /// Which of the layouts this is
+    fn kind(&self) -> LayoutKind;
+    /// Whether clients cover each other, so the focused one has to be raised
+    fn raise_focused(&self) -> bool {
+        false
+    }
+}
+
+/// Splits `length` px starting at `start` into `count` parts with `gaps` px between them.
+/// Returns the start and length of every part; pixels which can't be divided evenly
+/// are handed out to the first parts, so together they always cover the whole length
+pub fn split(start: i32, length: i32, count: usize, gaps: i32) -> Vec<(i32, i32)> {
+    if count == 0 {
+        return vec![];
+    }
+    let count = count as i32;
+    let usable = length - (count - 1) * gaps;
+    let (size, leftover) = (usable / count, usable % count);
+
+    let mut offset = start;
+    (0..count)
+        .map(|index| {
+            let part = size + if index < leftover { 1 } else { 0 };
+            let result = (offset, part);
+            offset += part + gaps;
+            result
+        })
+        .collect()
+}
+
+/// Stacks `count` clients on top of each other within the column
+pub fn column(area: Rect, count: usize, gaps: i32) -> Vec<Rect> {
+    split(area.y, area.height, count, gaps)
+        .into_iter()
+        .map(|(y, height)| Rect::new(area.x, y, area.width, height))
+        .collect()
+}
+
+/// Splits the area into the master column on the left (`ratio` of the usable width)
+/// and the stack column on the right
+pub fn split_master(area: Rect, ratio: f32, gaps: i32) -> (Rect, Rect) {
+    let usable_width = area.width - gaps;
+    let master_width = (usable_width as f32 * ratio) as i32;
+    let master = Rect::new(area.x, area.y, master_width, area.height);
+    let stack = Rect::new(
+        area.x + master_width + gaps,
+        area.y,
+        usable_width - master_width,
+        area.height,
     );
+    (master, stack)
+}
+
+/// Splits the clients into (masters, stack), with at most `master_count` masters
+pub fn split_clients(count: usize, master_count: usize) -> (usize, usize) {
+    let masters = master_count.min(count);
+    (masters, count - masters)
+}
+
+/// Arranges the masters in a column on the left and the stack in a column on the right.
+/// If there is only one kind of client, they all share a single column
+pub fn master_and_stack(
+    area: Rect,
+    masters: usize,
+    stack: usize,
+    params: &LayoutParams,
+) -> Vec<Rect> {
+    let gaps = params.gaps;
+    if masters == 0 || stack == 0 {
+        return column(area, masters + stack, gaps);
+    }
+
+    let (master_area, stack_area) = split_master(area, params.master_ratio, gaps);
+    let mut rects = column(master_area, masters, gaps);
+    rects.extend(column(stack_area, stack, gaps));
+    rects
+}
+
+/// The available layouts, as named in the config
+#[derive(Deserialize, Serialize, Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub enum LayoutKind {
+    /// Master on the left, all other clients stacked on the right
+    Stack,
+    /// Every client fills the screen, only the focused one is visible
+    Monocle,
+    /// The clients are arranged in a grid of (nearly) as many rows as columns
+    Grid,
+    /// Every client takes a part of the remaining area, which then spirals inwards
+    Spiral,
+    /// Like `Spiral`, but the remaining area always shrinks towards the bottom right
+    Dwindle,
+    /// The master area in the middle, the other clients to the left and right of it
+    CenteredMaster,
+    /// The master area on the left, the other clients in two columns next to it
+    ThreeColumn,
+}
+
+impl LayoutKind {
+    pub fn build(&self) -> Box<dyn Layout> {
+        match self {
+            LayoutKind::Stack => Box::new(StackLayout::new()),
+            LayoutKind::Monocle => Box::new(MonocleLayout::new()),
+            LayoutKind::Grid => Box::new(GridLayout::new()),
+            LayoutKind::Spiral => Box::new(FibonacciLayout::spiral()),
+            LayoutKind::Dwindle => Box::new(FibonacciLayout::dwindle()),
+            LayoutKind::CenteredMaster => Box::new(CenteredMasterLayout::new()),
+            LayoutKind::ThreeColumn => Box::new(ThreeColumnLayout::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layouts_know_their_kind() {
+        let kinds = [
+            LayoutKind::Stack,
+            LayoutKind::Monocle,
+            LayoutKind::Grid,
+            LayoutKind::Spiral,
+            LayoutKind::Dwindle,
+            LayoutKind::CenteredMaster,
+            LayoutKind::ThreeColumn,
+        ];
+        for kind in kinds.iter() {
+            assert_eq!(kind.build().kind(), *kind);
+        }
+    }
 }
@@ -0,0 +1,50 @@
+use crate::layout::{Layout, LayoutKind, LayoutParams};
+use crate::models::Rect;
+
+/// Every client takes up the whole screen, stacked on top of each other
+pub struct MonocleLayout {}
+
+impl MonocleLayout {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Layout for MonocleLayout {
+    fn arrange(&self, area: Rect, count: usize, params: &LayoutParams) -> Vec<Rect> {
+        vec![area.shrink(params.gaps); count]
+    }
+
+    /// Shows how many clients are hidden behind each other, e.g. "[3]"
+    fn symbol(&self, count: usize) -> String {
+        format!("[{}]", count)
+    }
+
+    fn kind(&self) -> LayoutKind {
+        LayoutKind::Monocle
+    }
+
+    fn raise_focused(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_client_fills_the_area() {
+        let params = LayoutParams {
+            gaps: 10,
+            ..LayoutParams::default()
+        };
+        let area = Rect::new(0, 0, 1000, 800);
+        let full = Rect::new(10, 10, 980, 780);
+        assert_eq!(
+            MonocleLayout::new().arrange(area, 3, &params),
+            vec![full, full, full]
+        );
+        assert_eq!(MonocleLayout::new().symbol(3), "[3]");
+    }
+}
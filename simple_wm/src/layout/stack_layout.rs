@@ -1,8 +1,5 @@
-use crate::config::Config;
-use crate::layout::Layout;
-use crate::tag::Tag;
-use crate::window_system::WindowSystem;
-use x11::xlib::Window;
+use crate::layout::{master_and_stack, split_clients, Layout, LayoutKind, LayoutParams};
+use crate::models::Rect;
 
 pub struct StackLayout {}
 
@@ -13,70 +10,112 @@ impl StackLayout {
 }
 
 impl Layout for StackLayout {
-    fn resize(&mut self, tag: &mut Tag, config: &Config, system: &WindowSystem) {
-        let mut offset_y: i32 = config.gaps as i32;
-        let window_width = (system.width / 2) - (config.gaps / 2) as i32 - (config.gaps as i32);
-        let left_window_height: i32 = system.height - (config.gaps * 2) as i32;
-        let offset_x = window_width + 2 * config.gaps as i32;
+    fn arrange(&self, area: Rect, count: usize, params: &LayoutParams) -> Vec<Rect> {
+        let (masters, stack) = split_clients(count, params.master_count);
+        // masters on the left, the rest stacked on the right
+        master_and_stack(area.shrink(params.gaps), masters, stack, params)
+    }
+
+    fn symbol(&self, _count: usize) -> String {
+        "[]=".to_string()
+    }
+
+    fn kind(&self) -> LayoutKind {
+        LayoutKind::Stack
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: Rect = Rect {
+        x: 0,
+        y: 0,
+        width: 1000,
+        height: 800,
+    };
+
+    fn params() -> LayoutParams {
+        LayoutParams {
+            gaps: 10,
+            ..LayoutParams::default()
+        }
+    }
+
+    #[test]
+    fn no_clients() {
+        assert!(StackLayout::new().arrange(SCREEN, 0, &params()).is_empty());
+    }
 
-        tag.for_root_and_remainder(
-            // only one client
-            |client| {
-                client.set_bounds(
-                    system,
-                    config.gaps as i32,
-                    config.gaps as i32,
-                    system.width - (config.gaps * 2) as i32,
-                    system.height - (config.gaps * 2) as i32,
-                );
-            },
-            // more than one client; left
-            |client, _| {
-                // set first client
-                client.set_bounds(
-                    system,
-                    config.gaps as i32,
-                    config.gaps as i32,
-                    window_width,
-                    left_window_height,
-                );
-            },
-            // more than one client; right
-            |client, len| {
-                let right_window_height: i32 =
-                    (system.height - (len * config.gaps) as i32) / (len - 1) as i32;
+    #[test]
+    fn single_client_fills_the_screen() {
+        assert_eq!(
+            StackLayout::new().arrange(SCREEN, 1, &params()),
+            vec![Rect::new(10, 10, 980, 780)]
+        );
+    }
 
-                // set the second client
-                client.set_bounds(
-                    system,
-                    offset_x,
-                    offset_y as i32,
-                    window_width,
-                    right_window_height,
-                );
-                // add the height
-                offset_y += right_window_height as i32;
-                // add the gaps
-                offset_y += config.gaps as i32;
-            },
+    #[test]
+    fn master_on_the_left_stack_on_the_right() {
+        assert_eq!(
+            StackLayout::new().arrange(SCREEN, 3, &params()),
+            vec![
+                Rect::new(10, 10, 485, 780),
+                Rect::new(505, 10, 485, 385),
+                Rect::new(505, 405, 485, 385),
+            ]
         );
     }
-    fn shift_left(
-        &mut self,
-        tag: &mut Tag,
-        shift_by: usize,
-        system: &WindowSystem,
-        current: Option<Window>,
-    ) {
-        println!("shifting left");
+
+    #[test]
+    fn stack_covers_the_whole_height() {
+        let rects = StackLayout::new().arrange(SCREEN, 4, &params());
+        assert_eq!(
+            rects[1..].to_vec(),
+            vec![
+                Rect::new(505, 10, 485, 254),
+                Rect::new(505, 274, 485, 253),
+                Rect::new(505, 537, 485, 253),
+            ]
+        );
     }
-    fn shift_right(
-        &mut self,
-        tag: &mut Tag,
-        shift_by: usize,
-        system: &WindowSystem,
-        current: Option<Window>,
-    ) {
-        println!("shifting right");
+
+    #[test]
+    fn multiple_masters() {
+        let params = LayoutParams {
+            master_count: 2,
+            ..params()
+        };
+        assert_eq!(
+            StackLayout::new().arrange(SCREEN, 3, &params),
+            vec![
+                Rect::new(10, 10, 485, 385),
+                Rect::new(10, 405, 485, 385),
+                Rect::new(505, 10, 485, 780),
+            ]
+        );
+
+        // without a stack or without masters, all clients share a single column
+        let rects = StackLayout::new().arrange(SCREEN, 2, &params);
+        assert_eq!(rects[0], Rect::new(10, 10, 980, 385));
+        let params = LayoutParams {
+            master_count: 0,
+            ..params
+        };
+        assert_eq!(StackLayout::new().arrange(SCREEN, 2, &params), rects);
+    }
+
+    #[test]
+    fn master_ratio_and_offset_area() {
+        let params = LayoutParams {
+            master_ratio: 0.25,
+            ..params()
+        };
+        let area = Rect::new(100, 50, 1000, 800);
+        assert_eq!(
+            StackLayout::new().arrange(area, 2, &params),
+            vec![Rect::new(110, 60, 242, 780), Rect::new(362, 60, 728, 780),]
+        );
     }
 }
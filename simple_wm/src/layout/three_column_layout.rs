@@ -0,0 +1,87 @@
+use crate::layout::{
+    column, master_and_stack, split, split_clients, split_master, Layout, LayoutKind, LayoutParams,
+};
+use crate::models::Rect;
+
+/// The master area on the left, the other clients split between two columns next to it:
+/// the first half in the middle one, the rest in the right one.
+/// With a single stack client, it behaves like the stack layout
+pub struct ThreeColumnLayout {}
+
+impl ThreeColumnLayout {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Layout for ThreeColumnLayout {
+    fn arrange(&self, area: Rect, count: usize, params: &LayoutParams) -> Vec<Rect> {
+        let gaps = params.gaps;
+        let inner = area.shrink(gaps);
+        let (masters, stack) = split_clients(count, params.master_count);
+        // one kind of client or a single stack client --> like the stack layout
+        if masters == 0 || stack <= 1 {
+            return master_and_stack(inner, masters, stack, params);
+        }
+
+        let (master_area, stack_area) = split_master(inner, params.master_ratio, gaps);
+        let mut rects = column(master_area, masters, gaps);
+
+        let middle = stack.div_ceil(2);
+        let columns = split(stack_area.x, stack_area.width, 2, gaps);
+        for ((x, width), count) in columns.into_iter().zip([middle, stack - middle]) {
+            let area = Rect::new(x, stack_area.y, width, stack_area.height);
+            rects.extend(column(area, count, gaps));
+        }
+        rects
+    }
+
+    fn symbol(&self, _count: usize) -> String {
+        "|||".to_string()
+    }
+
+    fn kind(&self) -> LayoutKind {
+        LayoutKind::ThreeColumn
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn master_and_two_columns() {
+        let params = LayoutParams {
+            gaps: 10,
+            ..LayoutParams::default()
+        };
+        assert_eq!(
+            ThreeColumnLayout::new().arrange(Rect::new(0, 0, 1000, 800), 4, &params),
+            vec![
+                Rect::new(10, 10, 485, 780),
+                Rect::new(505, 10, 238, 385),
+                Rect::new(505, 405, 238, 385),
+                Rect::new(753, 10, 237, 780),
+            ]
+        );
+    }
+
+    #[test]
+    fn master_ratio_and_count() {
+        let params = LayoutParams {
+            master_ratio: 0.25,
+            master_count: 2,
+            ..LayoutParams::default()
+        };
+        let rects = ThreeColumnLayout::new().arrange(Rect::new(0, 0, 1000, 800), 4, &params);
+        assert_eq!(
+            rects,
+            vec![
+                Rect::new(0, 0, 250, 400),
+                Rect::new(0, 400, 250, 400),
+                Rect::new(250, 0, 375, 800),
+                Rect::new(625, 0, 375, 800),
+            ]
+        );
+    }
+}
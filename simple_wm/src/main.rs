@@ -1,6 +1,9 @@
 #![allow(non_upper_case_globals)]
+mod backend;
+mod cli;
 mod client;
 mod config;
+mod config_watcher;
 mod key_handler;
 mod layout;
 mod models;
@@ -8,9 +11,27 @@ mod tag;
 mod window_manager;
 mod window_system;
 
+use cli::Args;
+use config::Config;
+use std::process;
 use window_manager::WindowManager;
 
 fn main() {
-    let mut wm = WindowManager::new();
+    let args = Args::from_env();
+    let config = match Config::load(args.config.as_deref()) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("simple_wm: {}", err);
+            process::exit(1);
+        }
+    };
+
+    let mut wm = match WindowManager::new(config, args) {
+        Ok(wm) => wm,
+        Err(err) => {
+            eprintln!("simple_wm: {}", err);
+            process::exit(1);
+        }
+    };
     wm.run();
 }
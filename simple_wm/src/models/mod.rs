@@ -1,4 +1,210 @@
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Eq, PartialEq, Hash, Debug)]
 pub enum Direction {
     Left,
-    Right
-} 
+    Right,
+    Up,
+    Down,
+}
+
+/// An area on screen in px
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// The area with `gap` px taken away on each side
+    pub fn shrink(&self, gap: i32) -> Self {
+        Self::new(
+            self.x + gap,
+            self.y + gap,
+            self.width - 2 * gap,
+            self.height - 2 * gap,
+        )
+    }
+}
+
+/// The size constraints of a window from WM_NORMAL_HINTS, 0 where it has none
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Default)]
+pub struct SizeHints {
+    pub base_width: i32,
+    pub base_height: i32,
+    pub min_width: i32,
+    pub min_height: i32,
+    pub max_width: i32,
+    pub max_height: i32,
+    pub width_inc: i32,
+    pub height_inc: i32,
+    /// The smallest & largest width/height ratio, as (width, height)
+    pub min_aspect: (i32, i32),
+    pub max_aspect: (i32, i32),
+}
+
+impl SizeHints {
+    /// Whether the window can't be resized at all
+    pub fn is_fixed(&self) -> bool {
+        self.min_width > 0
+            && self.min_height > 0
+            && self.min_width == self.max_width
+            && self.min_height == self.max_height
+    }
+
+    /// The size closest to the given one the window accepts (see ICCCM section 4.1.2.3)
+    pub fn apply(&self, width: i32, height: i32) -> (i32, i32) {
+        let mut width = width;
+        let mut height = height;
+        // the base size doesn't count for the aspect ratio, unless it is the min size
+        let base_is_min = self.base_width == self.min_width && self.base_height == self.min_height;
+        if !base_is_min {
+            width -= self.base_width;
+            height -= self.base_height;
+        }
+        let (min_x, min_y) = self.min_aspect;
+        let (max_x, max_y) = self.max_aspect;
+        if min_x > 0 && min_y > 0 && max_x > 0 && max_y > 0 && width > 0 && height > 0 {
+            let max_ratio = max_x as f32 / max_y as f32;
+            let min_ratio = min_x as f32 / min_y as f32;
+            let ratio = width as f32 / height as f32;
+            if ratio > max_ratio {
+                width = (height as f32 * max_ratio).round() as i32;
+            } else if ratio < min_ratio {
+                height = (width as f32 / min_ratio).round() as i32;
+            }
+        }
+        if base_is_min {
+            width -= self.base_width;
+            height -= self.base_height;
+        }
+
+        // whole increments on top of the base size
+        if self.width_inc > 0 {
+            width -= width.rem_euclid(self.width_inc);
+        }
+        if self.height_inc > 0 {
+            height -= height.rem_euclid(self.height_inc);
+        }
+        width = (width + self.base_width).max(self.min_width).max(1);
+        height = (height + self.base_height).max(self.min_height).max(1);
+        if self.max_width > 0 {
+            width = width.min(self.max_width);
+        }
+        if self.max_height > 0 {
+            height = height.min(self.max_height);
+        }
+        (width, height)
+    }
+}
+
+/// A color, written as "#rrggbb" in the config
+#[derive(Deserialize, Serialize, Clone, Copy, Eq, PartialEq, Hash, Debug)]
+#[serde(try_from = "String", into = "String")]
+pub struct Color(pub u32);
+
+impl Color {
+    /// The pixel value of the color on a TrueColor visual
+    pub fn pixel(&self) -> u64 {
+        self.0 as u64
+    }
+
+    pub fn red(&self) -> u8 {
+        (self.0 >> 16) as u8
+    }
+
+    pub fn green(&self) -> u8 {
+        (self.0 >> 8) as u8
+    }
+
+    pub fn blue(&self) -> u8 {
+        self.0 as u8
+    }
+}
+
+/// A color which isn't written as "#rrggbb"
+#[derive(Debug, Eq, PartialEq)]
+pub struct ColorParseError(pub String);
+
+impl fmt::Display for ColorParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid color \"{}\", expected #rrggbb", self.0)
+    }
+}
+
+impl std::error::Error for ColorParseError {}
+
+impl TryFrom<String> for Color {
+    type Error = ColorParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let digits = value.strip_prefix('#').unwrap_or("");
+        if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ColorParseError(value));
+        }
+        u32::from_str_radix(digits, 16)
+            .map(Color)
+            .map_err(|_| ColorParseError(value))
+    }
+}
+
+impl From<Color> for String {
+    fn from(color: Color) -> Self {
+        format!("#{:06x}", color.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_hints_round_down_to_increments() {
+        // a terminal with 7x14 px cells and 2 px padding
+        let hints = SizeHints {
+            base_width: 4,
+            base_height: 4,
+            width_inc: 7,
+            height_inc: 14,
+            ..SizeHints::default()
+        };
+        assert_eq!(hints.apply(500, 300), (494, 298));
+    }
+
+    #[test]
+    fn size_hints_limit_the_size() {
+        let hints = SizeHints {
+            min_width: 200,
+            min_height: 100,
+            max_width: 400,
+            max_height: 300,
+            ..SizeHints::default()
+        };
+        assert_eq!(hints.apply(100, 500), (200, 300));
+        assert!(!hints.is_fixed());
+    }
+
+    #[test]
+    fn size_hints_keep_the_aspect_ratio() {
+        let hints = SizeHints {
+            min_aspect: (16, 9),
+            max_aspect: (16, 9),
+            ..SizeHints::default()
+        };
+        assert_eq!(hints.apply(1600, 1200), (1600, 900));
+        assert_eq!(hints.apply(1000, 450), (800, 450));
+    }
+}
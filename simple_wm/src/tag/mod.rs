@@ -1,7 +1,14 @@
 mod tag;
 
 use crate::client::Client;
+use crate::layout::Layout;
 
 pub struct Tag {
     pub windows: Vec<Client>,
+    /// The layout the tag is arranged with, see `Layout::kind` for which one it is
+    pub layout: Box<dyn Layout>,
+    /// How much of the usable width the master area takes up
+    pub master_ratio: f32,
+    /// How many clients share the master area
+    pub master_count: usize,
 }
@@ -1,12 +1,22 @@
+use crate::backend::Backend;
 use crate::client::Client;
+use crate::layout::LayoutKind;
+use crate::models::Direction;
 use crate::tag::Tag;
-use crate::window_system::WindowSystem;
-use x11::xlib;
 use x11::xlib::Window;
 
 impl Tag {
     pub fn new() -> Self {
-        Self { windows: vec![] }
+        Self {
+            windows: vec![],
+            layout: LayoutKind::Stack.build(),
+            master_ratio: 0.5,
+            master_count: 1,
+        }
+    }
+
+    pub fn set_layout(&mut self, kind: LayoutKind) {
+        self.layout = kind.build();
     }
 
     pub fn add_new_window_if_not_exists(&mut self, client: Client) {
@@ -15,10 +25,28 @@ impl Tag {
         }
     }
 
-    fn window_contained(&self, window: &Window) -> bool {
+    pub fn window_contained(&self, window: &Window) -> bool {
         self.windows.iter().any(|current| current.window == *window)
     }
 
+    pub fn get_client(&self, window: &Window) -> Option<&Client> {
+        self.windows.iter().find(|client| client.window == *window)
+    }
+
+    pub fn get_client_mut(&mut self, window: &Window) -> Option<&mut Client> {
+        self.windows
+            .iter_mut()
+            .find(|client| client.window == *window)
+    }
+
+    /// How many clients are laid out, i.e. not floating
+    pub fn tiled_count(&self) -> usize {
+        self.windows
+            .iter()
+            .filter(|client| !client.floating)
+            .count()
+    }
+
     pub fn get_windows(&self) -> &[Client] {
         &self.windows[..]
     }
@@ -27,7 +55,7 @@ impl Tag {
         &mut self.windows[..]
     }
 
-    pub fn map_window(&self, window: &Window, system: &WindowSystem) {
+    pub fn map_window(&self, window: &Window, system: &dyn Backend) {
         // map the windows with the same id
         self.windows
             .iter()
@@ -38,53 +66,181 @@ impl Tag {
             });
     }
 
-    pub fn set_focus(&self, window: &Window, system: &WindowSystem) {
-        unsafe {
-            xlib::XSetInputFocus(
-                system.display,
-                *window,
-                xlib::RevertToParent,
-                xlib::CurrentTime,
-            );
-        }
+    pub fn set_focus(&self, window: &Window, system: &dyn Backend) {
+        system.focus(*window);
     }
 
-    pub fn for_root_and_remainder<R, RS, S>(
-        &mut self,
-        mut root: R,
-        mut root_with_sub: RS,
-        mut remainder: S,
-    ) where
-        R: FnMut(&mut Client),
-        RS: FnMut(&mut Client, usize),
-        S: FnMut(&mut Client, usize),
-    {
+    fn position(&self, window: &Window) -> Option<usize> {
+        self.windows
+            .iter()
+            .position(|current| current.window == *window)
+    }
+
+    /// The window after (or before) the given one in stacking order, wrapping around.
+    /// As the master is the last element of `windows`, stacking order is the reverse
+    pub fn next_window(&self, window: &Window, forward: bool) -> Option<Window> {
         let len = self.windows.len();
-        let mut iter = self.windows.iter_mut().rev();
-        match (iter.next(), iter.next()) {
-            // no window contained
-            (None, None) => (),
-            // literally impossible
-            (None, Some(_)) => (),
-            (Some(client), None) => {
-                root(client);
-            }
-            (Some(first_client), Some(second_client)) => {
-                // set first client
-                root_with_sub(first_client, len);
+        let index = self.position(window)?;
+        let next = if forward {
+            (index + len - 1) % len
+        } else {
+            (index + 1) % len
+        };
+        Some(self.windows[next].window)
+    }
 
-                remainder(second_client, len);
+    /// The window which is geometrically closest to the given one in that direction.
+    /// Windows overlapping it on the other axis are preferred, then the closest one wins
+    pub fn neighbour(&self, window: &Window, direction: Direction) -> Option<Window> {
+        let current = &self.windows[self.position(window)?];
+        let (current_x, current_y) = current.center();
 
-                // for all the remaining windows on the right side
-                for client in iter {
-                    remainder(client, len);
+        self.windows
+            .iter()
+            .filter(|client| client.window != *window)
+            .filter_map(|client| {
+                let (x, y) = client.center();
+                // distance along the direction & whether the other axis overlaps
+                let (distance, offset, overlaps) = match direction {
+                    Direction::Left => (
+                        current_x - x,
+                        y - current_y,
+                        current.overlaps_vertically(client),
+                    ),
+                    Direction::Right => (
+                        x - current_x,
+                        y - current_y,
+                        current.overlaps_vertically(client),
+                    ),
+                    Direction::Up => (
+                        current_y - y,
+                        x - current_x,
+                        current.overlaps_horizontally(client),
+                    ),
+                    Direction::Down => (
+                        y - current_y,
+                        x - current_x,
+                        current.overlaps_horizontally(client),
+                    ),
+                };
+                if distance > 0 {
+                    Some(((!overlaps, distance, offset.abs()), client.window))
+                } else {
+                    None
                 }
+            })
+            .min_by_key(|(score, _)| *score)
+            .map(|(_, window)| window)
+    }
+
+    /// Swaps the positions of both windows
+    pub fn swap_windows(&mut self, first: &Window, second: &Window) {
+        if let (Some(first), Some(second)) = (self.position(first), self.position(second)) {
+            self.windows.swap(first, second);
+        }
+    }
+
+    /// Swaps the window with the master; the master itself is swapped with the first stack client
+    pub fn zoom(&mut self, window: &Window) {
+        let len = self.windows.len();
+        if let Some(index) = self.position(window) {
+            if index + 1 < len {
+                self.windows.swap(index, len - 1);
+            } else if len > 1 {
+                self.windows.swap(index, len - 2);
             }
         }
     }
 
-    pub fn remove_window(&mut self, window: &Window) {
-        // delete all the matching windows
-        self.windows.retain(|current| current.window != *window);
+    /// Moves the window one position up (towards the master) or down in stacking order
+    pub fn move_window(&mut self, window: &Window, up: bool) {
+        let len = self.windows.len();
+        if let Some(index) = self.position(window) {
+            if up && index + 1 < len {
+                self.windows.swap(index, index + 1);
+            } else if !up && index > 0 {
+                self.windows.swap(index, index - 1);
+            }
+        }
+    }
+
+    /// Removes the client of the given window from the tag and hands it out
+    pub fn take_window(&mut self, window: &Window) -> Option<Client> {
+        let index = self.position(window)?;
+        Some(self.windows.remove(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag_with(windows: &[Window]) -> Tag {
+        let mut tag = Tag::new();
+        for window in windows {
+            tag.add_new_window_if_not_exists(Client::from_window(*window));
+        }
+        tag
+    }
+
+    fn windows(tag: &Tag) -> Vec<Window> {
+        tag.get_windows().iter().map(|c| c.window).collect()
+    }
+
+    #[test]
+    fn windows_are_only_added_once() {
+        let mut tag = tag_with(&[2, 3, 2]);
+        assert_eq!(windows(&tag), vec![2, 3]);
+        assert!(tag.window_contained(&3));
+
+        assert_eq!(tag.take_window(&3).map(|c| c.window), Some(3));
+        assert!(!tag.window_contained(&3));
+        assert_eq!(tag.take_window(&2).map(|c| c.window), Some(2));
+        assert!(tag.get_windows().is_empty());
+        assert_eq!(tag.take_window(&2), None);
+    }
+
+    #[test]
+    fn focus_cycles_in_stacking_order() {
+        // 4 is the master, followed by 3 and 2
+        let tag = tag_with(&[2, 3, 4]);
+        assert_eq!(tag.next_window(&4, true), Some(3));
+        assert_eq!(tag.next_window(&2, true), Some(4));
+        assert_eq!(tag.next_window(&4, false), Some(2));
+        assert_eq!(tag.next_window(&5, true), None);
+    }
+
+    #[test]
+    fn zoom_and_move() {
+        let mut tag = tag_with(&[2, 3, 4]);
+        tag.zoom(&2);
+        assert_eq!(windows(&tag), vec![4, 3, 2]);
+        // zooming the master swaps it with the next client
+        tag.zoom(&2);
+        assert_eq!(windows(&tag), vec![4, 2, 3]);
+
+        tag.move_window(&4, true);
+        assert_eq!(windows(&tag), vec![2, 4, 3]);
+        // the master cannot move up any further
+        tag.move_window(&3, true);
+        assert_eq!(windows(&tag), vec![2, 4, 3]);
+    }
+
+    #[test]
+    fn neighbours_by_geometry() {
+        let mut tag = tag_with(&[2, 3, 4]);
+        // master on the left, 3 above 2 on the right
+        let bounds = [(500, 500, 300), (500, 0, 500), (0, 0, 800)];
+        for (client, (x, y, height)) in tag.windows.iter_mut().zip(bounds.iter()) {
+            client.x = *x;
+            client.y = *y;
+            client.width = 500;
+            client.height = *height;
+        }
+
+        assert_eq!(tag.neighbour(&4, Direction::Right), Some(3));
+        assert_eq!(tag.neighbour(&3, Direction::Down), Some(2));
+        assert_eq!(tag.neighbour(&2, Direction::Left), Some(4));
+        assert_eq!(tag.neighbour(&4, Direction::Left), None);
     }
 }
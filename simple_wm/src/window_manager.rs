@@ -1,55 +1,808 @@
+use crate::backend::Backend;
+use crate::cli::Args;
 use crate::client::Client;
 use crate::config::Config;
+use crate::config_watcher::ConfigWatcher;
 use crate::key_handler::*;
-use crate::layout::stack_layout::StackLayout;
-use crate::layout::Layout;
+use crate::layout::{LayoutKind, LayoutParams, MIN_COLUMN_WIDTH};
+use crate::models::{Color, Direction, Rect};
 use crate::tag::Tag;
-use crate::window_system::WindowSystem;
+use crate::window_system::{WindowSystem, WindowSystemError};
 use std::mem::MaybeUninit;
-use std::os::raw::c_int;
-use std::os::raw::c_ulong;
+use std::os::raw::{c_int, c_long, c_uint, c_ulong};
+use std::path::PathBuf;
+use std::process;
+use std::ptr;
+use std::slice;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 use x11::xlib;
 use x11::xlib::Display;
-use x11::xlib::Window;
+use x11::xlib::{Atom, Window};
 
 use std::collections::*;
 
 pub const MAX_WORKSPACES: usize = 10;
 
-pub struct WindowManager {
+/// How long we wait for the old window manager with --replace
+const REPLACE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct WindowManager<B: Backend = WindowSystem> {
     /// A handle to the underlying X11 system
-    pub window_system: WindowSystem,
+    pub window_system: B,
     /// All the various tags with their respective clients
     pub tags: Vec<Tag>,
     /// The currently visible workspace -> from 0 to 9
     pub current_workspace: usize,
     /// The config for the window manager, can be reloaded
     pub config: Config,
-    /// The current layout
-    pub current_layout: Box<dyn Layout>,
+    /// The config file passed on the command line, if any
+    pub config_override: Option<PathBuf>,
+    /// Watches the config file if `Config::watch` is enabled
+    pub config_watcher: Option<ConfigWatcher>,
+    /// The layout symbol last published on the root window
+    pub layout_symbol: String,
     /// The currently focused window
     /// Some --> A window has focus; there is at least 1 window present
     /// None --> The root window has the focus
     pub current_window: Option<Window>,
+    /// The modifier bit NumLock is mapped to, ignored for key bindings
+    pub numlock_mask: u32,
+    /// The workspace which was visible before the current one
+    pub last_workspace: usize,
+    /// How many UnmapNotify events are caused by ourselves hiding the window;
+    /// these must not be mistaken for the client withdrawing it
+    pub pending_unmaps: HashMap<Window, usize>,
+    /// Replace another window manager on startup instead of exiting
+    pub replace: bool,
+    /// The window owning the WM_Sn selection; once we lose it, we exit
+    pub selection_window: Window,
 }
 
-impl WindowManager {
-    pub fn new() -> Self {
+/// Set by `detect_other_wm` if selecting SubstructureRedirectMask failed
+static OTHER_WM_RUNNING: AtomicBool = AtomicBool::new(false);
+
+impl<B: Backend> WindowManager<B> {
+    pub fn with_backend(window_system: B, config: Config, args: Args) -> Self {
         let mut tags = Vec::with_capacity(MAX_WORKSPACES);
-        for _ in 0..MAX_WORKSPACES {
-            tags.push(Tag::new());
+        for workspace in 0..MAX_WORKSPACES {
+            let mut tag = Tag::new();
+            tag.set_layout(config.layout_for(workspace));
+            tag.master_count = config.master_count_for(workspace);
+            tags.push(tag);
         }
         Self {
-            window_system: WindowSystem::new(),
+            window_system,
             tags,
             current_workspace: 0,
-            config: Config::new(),
-            current_layout: Box::new(StackLayout::new()),
+            config,
+            config_override: args.config,
+            config_watcher: None,
+            layout_symbol: String::new(),
             current_window: None,
+            numlock_mask: 0,
+            last_workspace: 0,
+            pending_unmaps: HashMap::new(),
+            replace: args.replace,
+            selection_window: 0,
+        }
+    }
+
+    fn set_and_focus_current(&mut self, window: &Window) {
+        // not the root window
+        if *window != self.window_system.root() {
+            // focus the current window
+            self.window_system.focus(*window);
+            self.restack(window);
+            // set it to be the current window
+            self.current_window = Some(*window);
+            self.update_borders();
+        }
+    }
+
+    /// Keeps the floating clients of the current tag above the tiled ones.
+    /// The focused client goes on top of its kind, if it may be hidden behind the others
+    fn restack(&self, focused: &Window) {
+        let current_tag = &self.tags[self.current_workspace];
+        let focused = current_tag.get_client(focused);
+        if let Some(client) = focused {
+            if !client.floating && current_tag.layout.raise_focused() {
+                self.window_system.raise(client.frame);
+            }
+        }
+        for client in current_tag.get_windows() {
+            if client.floating && Some(client.window) != focused.map(|client| client.window) {
+                self.window_system.raise(client.frame);
+            }
+        }
+        if let Some(client) = focused.filter(|client| client.floating) {
+            self.window_system.raise(client.frame);
+        }
+    }
+
+    /// Colors the borders of the current tag: focused, urgent or unfocused.
+    /// Title bars take the same color, so they are drawn again if it changed
+    fn update_borders(&mut self) {
+        let focused = self.current_window;
+        let config = &self.config;
+        for client in self.tags[self.current_workspace].windows.iter_mut() {
+            let color = if Some(client.window) == focused {
+                config.focused_border_color
+            } else if client.urgent {
+                config.urgent_border_color
+            } else {
+                config.border_color
+            };
+            let text_color = Self::title_color(config, focused, client);
+            // the text color may change on its own, e.g. if urgent & focused borders look alike
+            let border_changed = client.set_border_color(&self.window_system, color);
+            if border_changed || client.title_color != Some(text_color) {
+                client.draw_title_bar(&self.window_system, text_color);
+            }
+        }
+    }
+
+    /// The text color of the client's title
+    fn title_color(config: &Config, focused: Option<Window>, client: &Client) -> Color {
+        if Some(client.window) == focused {
+            config.focused_title_color
+        } else {
+            config.title_color
+        }
+    }
+
+    /// Draws the title bars of the current tag matching the predicate again
+    fn redraw_title_bars<F>(&mut self, predicate: F)
+    where
+        F: Fn(&Client) -> bool,
+    {
+        for client in self.tags[self.current_workspace].windows.iter_mut() {
+            if predicate(client) {
+                let text_color = Self::title_color(&self.config, self.current_window, client);
+                client.draw_title_bar(&self.window_system, text_color);
+            }
+        }
+    }
+
+    /// Loads the font of the title bars, if there are any
+    fn load_title_font(&self) {
+        if self.config.title_bars && !self.window_system.load_font(&self.config.title_font) {
+            eprintln!("simple_wm: cannot load font {}", self.config.title_font);
+        }
+    }
+
+    /// Focuses the master of the current tag, or the root window if the tag is empty
+    fn focus_current_tag(&mut self) {
+        let windows = self.tags[self.current_workspace].get_windows();
+        let next_window = windows.iter().rev().next().map(|client| client.window);
+
+        // at least  1 window present --> focus it!
+        if let Some(window) = next_window {
+            self.set_and_focus_current(&window);
+        }
+        // none present --> focused window is root window!
+        else {
+            self.current_window = None;
+            self.update_borders();
+        }
+    }
+
+    /// The area of the screen the layouts may use
+    fn screen_area(&self) -> Rect {
+        let (width, height) = self.window_system.screen_size();
+        Rect::new(0, 0, width, height)
+    }
+
+    /// Lays out the current tag again, only touching clients whose geometry changed
+    fn resize_current(&mut self) {
+        let area = self.screen_area();
+        let current_tag = &mut self.tags[self.current_workspace];
+        let params = LayoutParams {
+            gaps: self.config.gaps as i32,
+            master_ratio: current_tag.master_ratio,
+            master_count: current_tag.master_count,
+            split_ratio: self.config.split_ratio,
+            min_client_size: self.config.min_client_size as i32,
+        };
+        let rects = current_tag
+            .layout
+            .arrange(area, current_tag.tiled_count(), &params);
+
+        // the layout starts with the master, which is the last client;
+        // floating clients stay where they are
+        let tiled = current_tag
+            .windows
+            .iter_mut()
+            .rev()
+            .filter(|client| !client.floating);
+        for (client, rect) in tiled.zip(rects) {
+            let rect = if self.config.tiled_size_hints {
+                client.constrain(rect)
+            } else {
+                rect
+            };
+            client.set_bounds(&self.window_system, rect);
+        }
+        self.publish_layout_symbol();
+    }
+
+    /// Stores the symbol of the current layout in the _SIMPLE_WM_LAYOUT property
+    /// of the root window, so status bars can show it
+    fn publish_layout_symbol(&mut self) {
+        let current_tag = &self.tags[self.current_workspace];
+        let symbol = current_tag.layout.symbol(current_tag.tiled_count());
+        if symbol != self.layout_symbol {
+            let root = self.window_system.root();
+            let property = self.window_system.atoms().simple_wm_layout;
+            self.window_system
+                .set_text_property(root, property, &symbol);
+            self.layout_symbol = symbol;
+        }
+    }
+
+    /// Arranges the current tag with the given layout
+    pub fn set_layout(&mut self, kind: LayoutKind) {
+        self.tags[self.current_workspace].set_layout(kind);
+        self.resize_current();
+        // e.g. monocle needs the focused client on top
+        if let Some(window) = self.current_window {
+            self.set_and_focus_current(&window);
+        }
+    }
+
+    /// Switches the current tag to the layout after its current one in `Config::layouts`
+    pub fn cycle_layout(&mut self) {
+        let layouts = &self.config.layouts;
+        let current = self.tags[self.current_workspace].layout.kind();
+        // a layout which isn't part of the list starts the cycle from the beginning
+        let next = match layouts.iter().position(|kind| *kind == current) {
+            Some(index) => layouts.get((index + 1) % layouts.len()),
+            None => layouts.first(),
+        };
+        if let Some(kind) = next.cloned() {
+            self.set_layout(kind);
+        }
+    }
+
+    /// Puts one client more (or less) into the master area of the current tag.
+    /// There are never more masters than clients, so every press has a visible effect
+    pub fn adjust_master_count(&mut self, increase: bool) {
+        let current_tag = &mut self.tags[self.current_workspace];
+        // an empty tag still keeps one master for the next client
+        let max_count = current_tag.tiled_count().max(1);
+        let master_count = current_tag.master_count.min(max_count);
+        current_tag.master_count = if increase {
+            (master_count + 1).min(max_count)
+        } else {
+            master_count.saturating_sub(1)
+        };
+        self.resize_current();
+    }
+
+    /// Moves the border between master and stack by `amount` px
+    pub fn shift_master(&mut self, amount: i32) {
+        let usable_width = (self.screen_area().width - 3 * self.config.gaps as i32) as f32;
+        if usable_width <= 2.0 * MIN_COLUMN_WIDTH as f32 {
+            return;
+        }
+        let min_ratio = MIN_COLUMN_WIDTH as f32 / usable_width;
+
+        let current_tag = &mut self.tags[self.current_workspace];
+        current_tag.master_ratio = (current_tag.master_ratio + amount as f32 / usable_width)
+            .max(min_ratio)
+            .min(1.0 - min_ratio);
+        self.resize_current();
+    }
+
+    /// Floats the focused client or puts it back into the layout.
+    /// A client floating again gets back the geometry it had when it last floated
+    pub fn toggle_floating(&mut self) {
+        let window = match self.current_window {
+            Some(window) => window,
+            None => return,
+        };
+        let current_tag = &mut self.tags[self.current_workspace];
+        let client = match current_tag.get_client_mut(&window) {
+            Some(client) => client,
+            None => return,
+        };
+        if client.floating {
+            client.floating = false;
+            client.floating_bounds = Some(client.bounds());
+        } else {
+            client.floating = true;
+            // the first time, it just stays where the layout put it
+            let bounds =
+                client.constrain(client.floating_bounds.unwrap_or_else(|| client.bounds()));
+            client.set_bounds(&self.window_system, bounds);
+        }
+        self.resize_current();
+        self.set_and_focus_current(&window);
+    }
+
+    /// Where a window starts floating: where it asked to be, or centered if it didn't ask.
+    /// Either way, it is kept within the screen
+    fn initial_floating_bounds(&self, client: &Client, geometry: Option<Rect>) -> Rect {
+        let area = self.screen_area();
+        let mut window =
+            geometry.unwrap_or_else(|| Rect::new(0, 0, area.width / 2, area.height / 2));
+        let (width, height) = client.size_hints.apply(window.width, window.height);
+        window.width = width;
+        window.height = height;
+
+        let mut bounds = client.frame_bounds(window);
+        bounds.width = bounds.width.min(area.width);
+        bounds.height = bounds.height.min(area.height);
+        if window.x == 0 && window.y == 0 {
+            bounds.x = area.x + (area.width - bounds.width) / 2;
+            bounds.y = area.y + (area.height - bounds.height) / 2;
+        }
+        bounds.x = bounds.x.max(area.x).min(area.x + area.width - bounds.width);
+        bounds.y = bounds
+            .y
+            .max(area.y)
+            .min(area.y + area.height - bounds.height);
+        bounds
+    }
+
+    /// Starts managing the window on the current tag by reparenting it into a frame.
+    /// Dialogs (transient windows) and fixed-size windows start floating
+    fn manage(&mut self, window: Window, mapped: bool) {
+        let mut client = Client::from_window(window);
+        // the geometry is relative to the root window only before reparenting
+        let geometry = self.window_system.window_geometry(window);
+        // reparenting a mapped window unmaps it for a moment
+        if mapped {
+            *self.pending_unmaps.entry(window).or_insert(0) += 1;
+        }
+        client.reparent(
+            &self.window_system,
+            self.config.border_width as i32,
+            self.config.title_height(),
+        );
+        self.window_system.watch_properties(window);
+        client.urgent = self.window_system.is_urgent(window);
+        client.title = self.window_system.window_title(window);
+        client.size_hints = self.window_system.size_hints(window);
+        if self.window_system.transient_for(window).is_some() || client.size_hints.is_fixed() {
+            client.floating = true;
+            let bounds = self.initial_floating_bounds(&client, geometry);
+            client.set_bounds(&self.window_system, bounds);
+        }
+        self.tags[self.current_workspace].add_new_window_if_not_exists(client);
+    }
+
+    /// Stops managing the window, e.g. because it was closed
+    fn unmanage(&mut self, window: &Window, destroyed: bool) {
+        let client = self.tags.iter_mut().find_map(|tag| tag.take_window(window));
+        // e.g. one of our frames
+        let mut client = match client {
+            Some(client) => client,
+            None => return,
+        };
+        self.pending_unmaps.remove(&client.frame);
+        client.unparent(&self.window_system, destroyed);
+
+        self.resize_current();
+        self.focus_current_tag();
+    }
+
+    /// Puts all clients back onto the root window, e.g. when another window manager takes over
+    /// or when shutting down
+    pub fn release_clients(&mut self) {
+        for tag in self.tags.iter_mut() {
+            for mut client in tag.windows.drain(..) {
+                client.unparent(&self.window_system, false);
+            }
+        }
+        self.current_window = None;
+    }
+
+    /// Unmaps the client's window without forgetting about it
+    fn hide_client(&mut self, client: &Client) {
+        *self.pending_unmaps.entry(client.frame).or_insert(0) += 1;
+        client.unmap(&self.window_system);
+    }
+
+    /// Returns true if the unmap of the window was caused by `hide_client`
+    fn consume_pending_unmap(&mut self, window: &Window) -> bool {
+        match self.pending_unmaps.get_mut(window) {
+            Some(count) if *count > 1 => {
+                *count -= 1;
+                true
+            }
+            Some(_) => {
+                self.pending_unmaps.remove(window);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Hides the current tag and shows the one with the given index
+    pub fn view_workspace(&mut self, index: usize) {
+        if index >= MAX_WORKSPACES || index == self.current_workspace {
+            return;
+        }
+
+        let hidden = self.tags[self.current_workspace].get_windows().to_vec();
+        for client in hidden.iter() {
+            self.hide_client(client);
+        }
+        self.last_workspace = self.current_workspace;
+        self.current_workspace = index;
+
+        self.resize_current();
+        for client in self.tags[self.current_workspace].get_windows() {
+            client.map(&self.window_system);
+        }
+        self.focus_current_tag();
+    }
+
+    /// Moves the focused client to the tag with the given index
+    pub fn move_to_workspace(&mut self, index: usize, follow: bool) {
+        let window = match self.current_window {
+            Some(window) => window,
+            None => return,
+        };
+        if index >= MAX_WORKSPACES || index == self.current_workspace {
+            return;
         }
+        let client = match self.tags[self.current_workspace].take_window(&window) {
+            Some(client) => client,
+            None => return,
+        };
+
+        if follow {
+            self.tags[index].add_new_window_if_not_exists(client);
+            self.view_workspace(index);
+            self.set_and_focus_current(&window);
+        } else {
+            self.hide_client(&client);
+            self.tags[index].add_new_window_if_not_exists(client);
+
+            self.resize_current();
+            self.focus_current_tag();
+        }
+    }
+
+    /// Moves the focus to the next/previous client of the current tag
+    pub fn focus_relative(&mut self, forward: bool) {
+        let current_tag = &self.tags[self.current_workspace];
+        let next = match self.current_window {
+            Some(window) => current_tag.next_window(&window, forward),
+            None => None,
+        };
+        // nothing focused (yet) --> start with the master
+        let next = next.or_else(|| current_tag.get_windows().last().map(|client| client.window));
+
+        if let Some(window) = next {
+            self.set_and_focus_current(&window);
+        }
+    }
+
+    /// Moves the focus to the closest client in the given direction
+    pub fn focus_direction(&mut self, direction: Direction) {
+        let next = match self.current_window {
+            Some(window) => self.tags[self.current_workspace].neighbour(&window, direction),
+            None => None,
+        };
+        if let Some(window) = next {
+            self.set_and_focus_current(&window);
+        }
+    }
+
+    /// Reorders the clients of the current tag around the focused one and lays them out again
+    pub fn rearrange_current<F>(&mut self, rearrange: F)
+    where
+        F: FnOnce(&mut Tag, &Window),
+    {
+        if let Some(window) = self.current_window {
+            rearrange(&mut self.tags[self.current_workspace], &window);
+            self.resize_current();
+            self.set_and_focus_current(&window);
+        }
+    }
+
+    fn on_configure_request(&mut self, conf_event: xlib::XConfigureRequestEvent) {
+        // windows living on another (hidden) tag stay there
+        let known = self
+            .tags
+            .iter()
+            .any(|tag| tag.window_contained(&conf_event.window));
+
+        // add it to the current tag
+        if !known {
+            self.manage(conf_event.window, false);
+        }
+        // also for new clients, which may have started floating
+        self.configure_floating(&conf_event);
+
+        // resize all the windows based on the current layout
+        self.resize_current();
+
+        // we don't grant the request to tiled clients, but have to answer it anyway
+        let client = self
+            .tags
+            .iter()
+            .find_map(|tag| tag.get_client(&conf_event.window));
+        if let Some(client) = client.filter(|client| known || client.floating) {
+            client.configure_notify(&self.window_system);
+        }
+    }
+
+    /// Floating clients get the geometry they ask for
+    fn configure_floating(&mut self, conf_event: &xlib::XConfigureRequestEvent) {
+        let client = self
+            .tags
+            .iter_mut()
+            .find_map(|tag| tag.get_client_mut(&conf_event.window));
+        let client = match client {
+            Some(client) if client.floating => client,
+            _ => return,
+        };
+        let mask = conf_event.value_mask;
+        let mut requested = client.window_bounds();
+        if mask & xlib::CWX as c_ulong != 0 {
+            requested.x = conf_event.x;
+        }
+        if mask & xlib::CWY as c_ulong != 0 {
+            requested.y = conf_event.y;
+        }
+        if mask & xlib::CWWidth as c_ulong != 0 {
+            requested.width = conf_event.width;
+        }
+        if mask & xlib::CWHeight as c_ulong != 0 {
+            requested.height = conf_event.height;
+        }
+        let bounds = client.constrain(client.frame_bounds(requested));
+        client.set_bounds(&self.window_system, bounds);
+    }
+
+    fn on_map_request(&mut self, window: &Window) {
+        // not every client sends a ConfigureRequest first
+        if !self.tags.iter().any(|tag| tag.window_contained(window)) {
+            self.manage(*window, false);
+            self.resize_current();
+        }
+        //Client is known in the current tag --> map it!
+        let current_tag = &self.tags[self.current_workspace];
+        current_tag.map_window(window, &self.window_system);
+        // make sure to focus the newly mapped window
+        self.set_and_focus_current(window);
+    }
+
+    fn on_unmap_notify(&mut self, window: &Window) {
+        // we hid the window ourselves (e.g. on a tag switch) --> keep it
+        if !self.consume_pending_unmap(window) {
+            self.unmanage(window, false);
+        }
+    }
+
+    fn on_destroy_notify(&mut self, window: &Window) {
+        // hidden windows are not unmapped again, so this is the only notice we get
+        self.pending_unmaps.remove(window);
+        self.unmanage(window, true);
+    }
+
+    fn on_property_notify(&mut self, window: &Window, atom: Atom) {
+        if atom == xlib::XA_WM_HINTS {
+            let urgent = self.window_system.is_urgent(*window);
+            for tag in self.tags.iter_mut() {
+                if let Some(client) = tag.get_client_mut(window) {
+                    client.urgent = urgent;
+                }
+            }
+            self.update_borders();
+        } else if atom == xlib::XA_WM_NORMAL_HINTS {
+            self.update_size_hints(window);
+        } else if atom == xlib::XA_WM_NAME || atom == self.window_system.atoms().net_wm_name {
+            let title = self.window_system.window_title(*window);
+            for tag in self.tags.iter_mut() {
+                if let Some(client) = tag.get_client_mut(window) {
+                    client.title = title.clone();
+                }
+            }
+            self.redraw_title_bars(|client| client.window == *window);
+        }
+    }
+
+    /// Re-reads WM_NORMAL_HINTS of the client. Like on manage, a client which became
+    /// fixed-size starts floating, keeping its position
+    fn update_size_hints(&mut self, window: &Window) {
+        let client = self.tags.iter().find_map(|tag| tag.get_client(window));
+        let mut client = match client {
+            Some(client) => client.clone(),
+            None => return,
+        };
+        client.size_hints = self.window_system.size_hints(*window);
+        let was_tiled = !client.floating;
+        let bounds = if was_tiled && client.size_hints.is_fixed() {
+            client.floating = true;
+            Some(self.initial_floating_bounds(&client, Some(client.window_bounds())))
+        } else if client.floating {
+            Some(client.constrain(client.bounds()))
+        } else {
+            None
+        };
+
+        let on_current_tag = self.tags[self.current_workspace].window_contained(window);
+        for tag in self.tags.iter_mut() {
+            if let Some(target) = tag.get_client_mut(window) {
+                *target = client.clone();
+                if let Some(bounds) = bounds {
+                    target.set_bounds(&self.window_system, bounds);
+                }
+            }
+        }
+        // hidden tags are laid out once they are shown again
+        if was_tiled && on_current_tag {
+            self.resize_current();
+            if let Some(focused) = self.current_window {
+                self.restack(&focused);
+            }
+        }
+    }
+
+    /// Parts of a frame became visible --> its title bar has to be drawn again
+    fn on_expose(&mut self, window: &Window) {
+        self.redraw_title_bars(|client| client.frame == *window);
+    }
+
+    /// Clicking a frame (i.e. the title bar or border) focuses its client
+    fn on_button_press(&mut self, window: &Window) {
+        let client = self.tags[self.current_workspace]
+            .get_windows()
+            .iter()
+            .find(|client| client.frame == *window)
+            .map(|client| client.window);
+        if let Some(client) = client {
+            self.set_and_focus_current(&client);
+        }
+    }
+
+    fn on_key_press(&mut self, event: &xlib::XKeyEvent) {
+        let modifiers =
+            KeyCombination::new(Modifier::from_event(event, self.numlock_mask), Key(0)).modifiers;
+        // several bound keys may share the keycode (e.g. q and Q) -->
+        // the one on the first level of the keycode wins
+        let first_level = self.window_system.keycode_to_keysym(event.keycode);
+
+        // (cloned, as the action may replace the config)
+        let action = self
+            .config
+            .key_bindings
+            .iter()
+            .filter(|(kc, _)| {
+                kc.modifiers == modifiers
+                    && self.window_system.keysym_to_keycode(kc.key.0) == event.keycode
+            })
+            .min_by_key(|(kc, _)| (kc.key.0 != first_level, kc.key.0))
+            .map(|(_, action)| action.clone());
+        if let Some(action) = action {
+            action.execute(self);
+        }
+    }
+
+    /// Re-reads the config file and applies it.
+    /// If the new config is invalid or the file is gone, the old one stays active
+    pub fn reload_config(&mut self) {
+        // falling back to the defaults would drop the user's key bindings
+        let path = match Config::locate(self.config_override.as_deref()) {
+            Some(path) => path,
+            None => {
+                eprintln!("simple_wm: not reloading the config: no config file found");
+                return;
+            }
+        };
+        let config = match Config::from_file(&path) {
+            Ok(config) => config,
+            Err(err) => {
+                eprintln!("simple_wm: not reloading the config: {}", err);
+                return;
+            }
+        };
+        self.apply_config(config);
+    }
+
+    /// Replaces the active config, grabbing the new key bindings
+    fn apply_config(&mut self, config: Config) {
+        let root = self.window_system.root();
+        self.ungrab_keybindings(&root);
+        // only tags whose configured layout changed are switched over
+        for (workspace, tag) in self.tags.iter_mut().enumerate() {
+            let layout = config.layout_for(workspace);
+            if layout != self.config.layout_for(workspace) {
+                tag.set_layout(layout);
+            }
+            let master_count = config.master_count_for(workspace);
+            if master_count != self.config.master_count_for(workspace) {
+                tag.master_count = master_count;
+            }
+        }
+        let border_width = config.border_width as i32;
+        let title_height = config.title_height();
+        for tag in self.tags.iter_mut() {
+            for client in tag.windows.iter_mut() {
+                client.set_decoration(&self.window_system, border_width, title_height);
+            }
+        }
+        let font_changed = config.title_font != self.config.title_font || !self.config.title_bars;
+        self.config = config;
+        if font_changed {
+            self.load_title_font();
+        }
+        self.register_keybindings(&root);
+        self.update_config_watcher();
+
+        // apply changed gaps, colors etc. right away
+        self.resize_current();
+        self.update_borders();
+        self.redraw_title_bars(|_| true);
+    }
+
+    /// Starts or stops watching the config file, depending on `Config::watch`
+    fn update_config_watcher(&mut self) {
+        if !self.config.watch {
+            self.config_watcher = None;
+            return;
+        }
+        if self.config_watcher.is_some() {
+            return;
+        }
+
+        match Config::locate(self.config_override.as_deref()) {
+            Some(path) => match ConfigWatcher::new(&path) {
+                Ok(watcher) => self.config_watcher = Some(watcher),
+                Err(err) => eprintln!("simple_wm: cannot watch {}: {}", path.display(), err),
+            },
+            None => eprintln!("simple_wm: no config file to watch"),
+        }
+    }
+
+    fn register_keybindings(&self, window: &Window) {
+        for binding in self.config.key_bindings.keys() {
+            let keycode = self.window_system.keysym_to_keycode(binding.key.0);
+            // grab every lock combination, otherwise CapsLock/NumLock break the binding
+            for mask in binding.get_masks(self.numlock_mask).iter() {
+                self.window_system.grab_key(keycode, *mask, *window);
+            }
+        }
+    }
+
+    fn ungrab_keybindings(&self, window: &Window) {
+        for binding in self.config.key_bindings.keys() {
+            let keycode = self.window_system.keysym_to_keycode(binding.key.0);
+            for mask in binding.get_masks(self.numlock_mask).iter() {
+                self.window_system.ungrab_key(keycode, *mask, *window);
+            }
+        }
+    }
+}
+
+/// Everything which only makes sense when talking to a real X server
+impl<B: Backend> Drop for WindowManager<B> {
+    /// Hands the clients back to the root window, so they survive us exiting
+    fn drop(&mut self) {
+        self.release_clients();
+    }
+}
+
+impl WindowManager {
+    pub fn new(config: Config, args: Args) -> Result<Self, WindowSystemError> {
+        let window_system = WindowSystem::new(args.display.as_deref())?;
+        Ok(Self::with_backend(window_system, config, args))
     }
 
-    fn get_next_event(&self) -> xlib::XEvent {
+    fn get_next_event(&mut self) -> xlib::XEvent {
+        // while watching the config, we must not block in XNextEvent
+        while self.config_watcher.is_some()
+            && unsafe { xlib::XPending(self.window_system.display) } == 0
+        {
+            self.wait_for_input();
+        }
+
         let event = unsafe {
             let mut event: xlib::XEvent = MaybeUninit::uninit().assume_init();
             xlib::XNextEvent(self.window_system.display, &mut event);
@@ -58,20 +811,36 @@ impl WindowManager {
         event
     }
 
-    fn set_and_focus_current(&mut self, window: &Window) {
-        // not the root window
-        if *window != self.window_system.root {
-            // focus the current window
-            unsafe {
-                xlib::XSetInputFocus(
-                    self.window_system.display,
-                    *window,
-                    xlib::RevertToPointerRoot,
-                    xlib::CurrentTime,
-                );
+    /// Blocks until either the X server or the config watcher has something for us
+    fn wait_for_input(&mut self) {
+        let watcher_fd = match &self.config_watcher {
+            Some(watcher) => watcher.fd(),
+            None => return,
+        };
+        let mut fds = [
+            libc::pollfd {
+                fd: unsafe { xlib::XConnectionNumber(self.window_system.display) },
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: watcher_fd,
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+        unsafe {
+            libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1);
+        }
+
+        if fds[1].revents & libc::POLLIN != 0 {
+            let changed = self
+                .config_watcher
+                .as_ref()
+                .is_some_and(|watcher| watcher.config_changed());
+            if changed {
+                self.reload_config();
             }
-            // set it to be the current window
-            self.current_window = Some(*window);
         }
     }
 
@@ -83,49 +852,31 @@ impl WindowManager {
                 xlib::ConfigureRequest => {
                     // convert to request
                     let conf_event = xlib::XConfigureRequestEvent::from(event);
-                    // get the current tag & make sure that it contains the new window
-                    let current_tag = &mut self.tags[self.current_workspace];
-
-                    // add it to the current tag
-                    current_tag.add_new_window_if_not_exists(Client::new(conf_event));
-
-                    // resize all the windows based on the current layout
-                    self.current_layout
-                        .resize(current_tag, &self.config, &self.window_system);
+                    self.on_configure_request(conf_event);
                 }
                 xlib::MapRequest => {
                     let map_event = xlib::XMapRequestEvent::from(event);
-                    //Client is already known in the current tag --> map it!
-                    let current_tag = &self.tags[self.current_workspace];
-                    current_tag.map_window(&map_event.window, &self.window_system);
-                    // make sure to focus the newly mapped window
-                    self.set_and_focus_current(&map_event.window);
+                    self.on_map_request(&map_event.window);
                 }
                 xlib::UnmapNotify => {
                     let map_event = xlib::XUnmapEvent::from(event);
-                    for tag in self.tags.iter_mut() {
-                        tag.remove_window(&map_event.window);
-                    }
-                    let current_tag = &mut self.tags[self.current_workspace];
-                    self.current_layout
-                        .resize(current_tag, &self.config, &self.window_system);
-
-                    // we are only cloning an arc here
-                    let windows = current_tag.get_windows();
-                    let next_window = windows.iter().rev().next().cloned();
-
-                    // at least  1 window present --> focus it!
-                    if let Some(client) = next_window {
-                        self.set_and_focus_current(&client.window);
-                    }
-                    // none present --> focused window is root window!
-                    else {
-                        self.current_window = None;
+                    self.on_unmap_notify(&map_event.window);
+                }
+                xlib::DestroyNotify => {
+                    let destroy_event = xlib::XDestroyWindowEvent::from(event);
+                    self.on_destroy_notify(&destroy_event.window);
+                }
+                xlib::ButtonPress => {
+                    let button_event = xlib::XButtonEvent::from(event);
+                    self.on_button_press(&button_event.window);
+                }
+                xlib::Expose => {
+                    let expose_event = xlib::XExposeEvent::from(event);
+                    // only once the last of a series of exposures arrived
+                    if expose_event.count == 0 {
+                        self.on_expose(&expose_event.window);
                     }
                 }
-                /*ButtonPress => {
-                    EventHandler::on_button_press(self, event);
-                }*/
                 /*xlib::MotionNotify => {
                     //skip any pending motion events
                     unsafe {
@@ -141,46 +892,41 @@ impl WindowManager {
                 }*/
                 xlib::KeyPress => {
                     let event: xlib::XKeyEvent = xlib::XKeyEvent::from(event);
-                    // get all the keys used within the bindings (todo: cache?)
-                    let used_keys: HashSet<&Key> = self
-                        .config
-                        .key_bindings
-                        .keys()
-                        .map(|entry| &entry.key)
-                        .collect();
-
-                    // try to find a matching key for the event
-                    let res: Option<&Key> = used_keys.iter().find_map(|item| unsafe {
-                        match event.keycode
-                            == xlib::XKeysymToKeycode(
+                    self.on_key_press(&event);
+                }
+                xlib::MappingNotify => {
+                    let mut mapping_evt = xlib::XMappingEvent::from(event);
+                    unsafe {
+                        xlib::XRefreshKeyboardMapping(&mut mapping_evt);
+                    }
+                    // keycodes or the NumLock modifier may have moved --> grab again
+                    if mapping_evt.request != xlib::MappingPointer {
+                        let root = self.window_system.root;
+                        unsafe {
+                            xlib::XUngrabKey(
                                 self.window_system.display,
-                                (**item) as c_ulong,
-                            )
-                            .into()
-                        {
-                            true => Some(*item),
-                            false => None,
-                        }
-                    });
-
-                    // key found --> get modifiers from event
-                    if let Some(key) = res {
-                        let kc = KeyCombination {
-                            modifiers: Modifier::from_event(&event),
-                            key: *key,
-                        };
-                        // if the combination is found --> execute its action
-                        if let Some(action) = self.config.key_bindings.get_mut(&kc) {
-                            action.execute(
-                                &self.window_system,
-                                self.config.shift_by,
-                                self.current_window,
-                                &mut *self.current_layout,
-                                &mut self.tags[self.current_workspace],
+                                xlib::AnyKey,
+                                xlib::AnyModifier,
+                                root,
                             );
                         }
+                        self.numlock_mask = Modifier::numlock_mask(self.window_system.display);
+                        self.register_keybindings(&root);
                     }
                 }
+                xlib::SelectionClear => {
+                    // another window manager took over via --replace
+                    let clear_evt = xlib::XSelectionClearEvent::from(event);
+                    if clear_evt.window == self.selection_window {
+                        eprintln!("simple_wm: replaced by another window manager");
+                        self.release_clients();
+                        return;
+                    }
+                }
+                xlib::PropertyNotify => {
+                    let property_evt = xlib::XPropertyEvent::from(event);
+                    self.on_property_notify(&property_evt.window, property_evt.atom);
+                }
                 xlib::EnterNotify => {
                     let crossing_evt = xlib::XCrossingEvent::from(event);
                     // can never be the root window!
@@ -193,6 +939,14 @@ impl WindowManager {
 
     pub fn init(&mut self) {
         unsafe {
+            xlib::XSetErrorHandler(Some(WindowManager::error_handler));
+        }
+        self.acquire_wm_selection();
+
+        // only one client may select SubstructureRedirectMask on the root window;
+        // if another window manager has done so, we get a BadAccess
+        unsafe {
+            xlib::XSetErrorHandler(Some(WindowManager::detect_other_wm));
             xlib::XSelectInput(
                 self.window_system.display,
                 self.window_system.root,
@@ -201,30 +955,159 @@ impl WindowManager {
             xlib::XSync(self.window_system.display, xlib::False);
             xlib::XSetErrorHandler(Some(WindowManager::error_handler));
         }
+        if OTHER_WM_RUNNING.load(Ordering::SeqCst) {
+            Self::fail("another window manager is already running");
+        }
         // register bindings for root window
+        self.numlock_mask = Modifier::numlock_mask(self.window_system.display);
         self.register_keybindings(&self.window_system.root);
+        self.update_config_watcher();
+        self.load_title_font();
+        self.adopt_existing_windows();
     }
 
-    fn register_keybindings(&self, window: &Window) {
-        for binding in self.config.key_bindings.keys() {
-            unsafe {
-                xlib::XGrabKey(
-                    self.window_system.display,
-                    xlib::XKeysymToKeycode(
-                        self.window_system.display,
-                        binding.key as std::os::raw::c_ulong,
-                    )
-                    .into(),
-                    binding.get_mask(),
-                    *window,
-                    0,
-                    xlib::GrabModeAsync,
-                    xlib::GrabModeAsync,
-                );
+    /// Takes over the WM_Sn selection as described in the ICCCM (section 2.8).
+    /// If another window manager owns it, it is only replaced with `--replace`
+    fn acquire_wm_selection(&mut self) {
+        let display = self.window_system.display;
+        let selection = self.window_system.atoms.wm_selection;
+
+        let owner = unsafe { xlib::XGetSelectionOwner(display, selection) };
+        if owner != 0 && !self.replace {
+            Self::fail("another window manager is already running (use --replace to replace it)");
+        }
+
+        let window = unsafe {
+            let window =
+                xlib::XCreateSimpleWindow(display, self.window_system.root, -1, -1, 1, 1, 0, 0, 0);
+            xlib::XSelectInput(display, window, xlib::PropertyChangeMask);
+            window
+        };
+        let timestamp = self.window_system.server_time(window);
+
+        unsafe {
+            // we want to know when the old window manager is gone
+            if owner != 0 {
+                xlib::XSelectInput(display, owner, xlib::StructureNotifyMask);
+            }
+            xlib::XSetSelectionOwner(display, selection, window, timestamp);
+            if xlib::XGetSelectionOwner(display, selection) != window {
+                Self::fail("could not acquire the window manager selection");
+            }
+        }
+
+        if owner != 0 {
+            self.wait_for_destruction(owner);
+        }
+
+        // announce ourselves as the new manager
+        unsafe {
+            let mut event: xlib::XEvent = MaybeUninit::zeroed().assume_init();
+            event.client_message.type_ = xlib::ClientMessage;
+            event.client_message.window = self.window_system.root;
+            event.client_message.message_type = self.window_system.atoms.manager;
+            event.client_message.format = 32;
+            event.client_message.data.set_long(0, timestamp as c_long);
+            event.client_message.data.set_long(1, selection as c_long);
+            event.client_message.data.set_long(2, window as c_long);
+            xlib::XSendEvent(
+                display,
+                self.window_system.root,
+                xlib::False,
+                xlib::StructureNotifyMask,
+                &mut event,
+            );
+        }
+        self.selection_window = window;
+    }
+
+    /// Waits (a few seconds at most) for the old window manager to give up its window
+    fn wait_for_destruction(&self, window: Window) {
+        let deadline = Instant::now() + REPLACE_TIMEOUT;
+        unsafe {
+            let mut event: xlib::XEvent = MaybeUninit::zeroed().assume_init();
+            while xlib::XCheckTypedWindowEvent(
+                self.window_system.display,
+                window,
+                xlib::DestroyNotify,
+                &mut event,
+            ) == 0
+            {
+                if Instant::now() > deadline {
+                    eprintln!("simple_wm: the old window manager did not exit in time");
+                    return;
+                }
+                thread::sleep(Duration::from_millis(20));
             }
         }
     }
 
+    fn fail(message: &str) -> ! {
+        eprintln!("simple_wm: {}", message);
+        process::exit(1);
+    }
+
+    /// Manages the windows which were already there before we started (e.g. after a restart)
+    fn adopt_existing_windows(&mut self) {
+        let display = self.window_system.display;
+        let mut children = vec![];
+        unsafe {
+            let mut root_return: Window = 0;
+            let mut parent_return: Window = 0;
+            let mut children_ptr: *mut Window = ptr::null_mut();
+            let mut count: c_uint = 0;
+            if xlib::XQueryTree(
+                display,
+                self.window_system.root,
+                &mut root_return,
+                &mut parent_return,
+                &mut children_ptr,
+                &mut count,
+            ) == 0
+            {
+                return;
+            }
+            if !children_ptr.is_null() {
+                children.extend_from_slice(slice::from_raw_parts(children_ptr, count as usize));
+                xlib::XFree(children_ptr as *mut _);
+            }
+        }
+
+        for window in children {
+            let attributes = unsafe {
+                let mut attributes: xlib::XWindowAttributes = MaybeUninit::zeroed().assume_init();
+                if xlib::XGetWindowAttributes(display, window, &mut attributes) == 0 {
+                    continue;
+                }
+                attributes
+            };
+            // popups & menus manage themselves, unmapped windows will send a MapRequest later on
+            if attributes.override_redirect != 0 || attributes.map_state != xlib::IsViewable {
+                continue;
+            }
+            if self.tags.iter().any(|tag| tag.window_contained(&window)) {
+                continue;
+            }
+            self.manage(window, true);
+        }
+
+        self.resize_current();
+        for client in self.tags[self.current_workspace].get_windows() {
+            client.map(&self.window_system);
+        }
+        self.focus_current_tag();
+    }
+
+    unsafe extern "C" fn detect_other_wm(
+        _display: *mut Display,
+        event: *mut xlib::XErrorEvent,
+    ) -> c_int {
+        if (*event).error_code == xlib::BadAccess {
+            OTHER_WM_RUNNING.store(true, Ordering::SeqCst);
+        }
+        0
+    }
+
     unsafe extern "C" fn error_handler(
         _display: *mut Display,
         event: *mut xlib::XErrorEvent,
@@ -233,3 +1116,722 @@ impl WindowManager {
         0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::fake::{Call, FakeBackend, FIRST_FRAME, ROOT};
+    use crate::layout::LayoutKind;
+    use crate::models::SizeHints;
+    use std::{env, fs};
+    use x11::keysym;
+
+    fn window_manager() -> WindowManager<FakeBackend> {
+        window_manager_with(Config::new())
+    }
+
+    fn window_manager_with(config: Config) -> WindowManager<FakeBackend> {
+        let args = Args {
+            config: None,
+            replace: false,
+            display: None,
+        };
+        WindowManager::with_backend(FakeBackend::new(1000, 800), config, args)
+    }
+
+    /// Goes through the same requests a newly mapped window causes
+    fn manage(wm: &mut WindowManager<FakeBackend>, window: Window) {
+        let mut event: xlib::XConfigureRequestEvent =
+            unsafe { MaybeUninit::zeroed().assume_init() };
+        event.window = window;
+        wm.on_configure_request(event);
+        wm.on_map_request(&window);
+    }
+
+    fn frame(wm: &WindowManager<FakeBackend>, window: Window) -> Window {
+        let client = wm.tags.iter().find_map(|tag| tag.get_client(&window));
+        client.map(|client| client.frame).unwrap()
+    }
+
+    fn press(wm: &mut WindowManager<FakeBackend>, state: u32, keysym: u32) {
+        let mut event: xlib::XKeyEvent = unsafe { MaybeUninit::zeroed().assume_init() };
+        event.state = state;
+        event.keycode = keysym;
+        wm.on_key_press(&event);
+    }
+
+    #[test]
+    fn close_prefers_wm_delete_window() {
+        let mut wm = window_manager();
+        let delete_window = wm.window_system.atoms.wm_delete_window;
+        wm.window_system.protocols.insert(2, vec![delete_window]);
+        manage(&mut wm, 2);
+        manage(&mut wm, 3);
+        wm.window_system.take_calls();
+
+        // CapsLock must not get in the way
+        let state = xlib::Mod1Mask | xlib::ShiftMask | xlib::LockMask;
+        press(&mut wm, state, keysym::XK_q);
+        assert_eq!(wm.window_system.take_calls(), vec![Call::Kill(3)]);
+
+        wm.current_window = Some(2);
+        press(&mut wm, state, keysym::XK_q);
+        assert_eq!(
+            wm.window_system.take_calls(),
+            vec![Call::SendProtocol(2, delete_window)]
+        );
+    }
+
+    #[test]
+    fn unbound_keys_are_ignored() {
+        let mut wm = window_manager();
+        manage(&mut wm, 2);
+        wm.window_system.take_calls();
+
+        press(&mut wm, xlib::ControlMask, keysym::XK_q);
+        press(&mut wm, xlib::Mod1Mask, keysym::XK_Escape);
+        assert!(wm.window_system.take_calls().is_empty());
+    }
+
+    #[test]
+    fn moving_between_workspaces() {
+        let mut wm = window_manager();
+        manage(&mut wm, 2);
+        manage(&mut wm, 3);
+
+        // Super+Shift+2 moves the focused window to the second workspace
+        press(&mut wm, xlib::Mod4Mask | xlib::ShiftMask, keysym::XK_2);
+        assert!(!wm.tags[0].window_contained(&3));
+        assert!(wm.tags[1].window_contained(&3));
+        assert_eq!(wm.current_window, Some(2));
+
+        // hiding it must not be mistaken for the client withdrawing the window
+        let hidden_frame = frame(&wm, 3);
+        wm.on_unmap_notify(&hidden_frame);
+        assert!(wm.tags[1].window_contained(&3));
+
+        // Super+2 shows it again
+        wm.window_system.take_calls();
+        press(&mut wm, xlib::Mod4Mask, keysym::XK_2);
+        assert_eq!(wm.current_workspace, 1);
+        let calls = wm.window_system.take_calls();
+        assert!(calls.contains(&Call::Unmap(frame(&wm, 2))));
+        assert!(calls.contains(&Call::Map(3)));
+        assert_eq!(wm.current_window, Some(3));
+    }
+
+    #[test]
+    fn key_bindings_are_grabbed_with_lock_masks() {
+        let mut wm = window_manager();
+        let mut key_bindings = HashMap::new();
+        let combination = KeyCombination::new(vec![Modifier::Super], keysym::XK_j.into());
+        key_bindings.insert(combination, Action::Builtin(BuiltinCommand::FocusNext));
+        wm.config.key_bindings = key_bindings;
+        wm.numlock_mask = xlib::Mod2Mask;
+
+        wm.register_keybindings(&ROOT);
+        let masks: Vec<u32> = wm
+            .window_system
+            .take_calls()
+            .into_iter()
+            .map(|call| match call {
+                Call::GrabKey { mask, .. } => mask,
+                call => panic!("unexpected call {:?}", call),
+            })
+            .collect();
+        let super_mask = xlib::Mod4Mask;
+        assert_eq!(
+            masks,
+            vec![
+                super_mask,
+                super_mask | xlib::LockMask,
+                super_mask | xlib::Mod2Mask,
+                super_mask | xlib::LockMask | xlib::Mod2Mask,
+            ]
+        );
+    }
+
+    #[test]
+    fn keys_sharing_a_keycode_are_told_apart() {
+        let mut wm = window_manager();
+        let bind = |modifiers, keysym: u32, layout| {
+            let combination = KeyCombination::new(modifiers, keysym.into());
+            (
+                combination,
+                Action::Builtin(BuiltinCommand::SetLayout(layout)),
+            )
+        };
+        wm.config.key_bindings = vec![
+            bind(vec![Modifier::Super], keysym::XK_q, LayoutKind::Grid),
+            bind(vec![Modifier::Super], keysym::XK_Q, LayoutKind::Monocle),
+            bind(
+                vec![Modifier::Super, Modifier::Shift],
+                keysym::XK_Q,
+                LayoutKind::Spiral,
+            ),
+        ]
+        .into_iter()
+        .collect();
+        wm.window_system
+            .keycodes
+            .insert(keysym::XK_Q.into(), keysym::XK_q);
+
+        // the key on the first level of the keycode wins
+        press(&mut wm, xlib::Mod4Mask, keysym::XK_q);
+        assert_eq!(wm.tags[0].layout.kind(), LayoutKind::Grid);
+        press(&mut wm, xlib::Mod4Mask | xlib::ShiftMask, keysym::XK_q);
+        assert_eq!(wm.tags[0].layout.kind(), LayoutKind::Spiral);
+    }
+
+    /// Reloads the config from the path, which must leave the current one active
+    fn assert_reload_keeps_config(path: PathBuf) {
+        let mut wm = window_manager();
+        let combination = KeyCombination::new(vec![Modifier::Super], keysym::XK_j.into());
+        let action = Action::Builtin(BuiltinCommand::FocusNext);
+        wm.config.key_bindings = vec![(combination.clone(), action.clone())]
+            .into_iter()
+            .collect();
+        wm.config.gaps = 7;
+        wm.config_override = Some(path);
+
+        wm.reload_config();
+        assert_eq!(wm.config.gaps, 7);
+        assert_eq!(wm.config.key_bindings.len(), 1);
+        assert_eq!(wm.config.key_bindings.get(&combination), Some(&action));
+        // the key bindings were not even ungrabbed
+        assert_eq!(wm.window_system.take_calls(), vec![]);
+    }
+
+    #[test]
+    fn invalid_config_is_not_reloaded() {
+        let path = env::temp_dir().join(format!("simple_wm_reload_{}.json", process::id()));
+        fs::write(&path, r#"{ "gaps": 3, "#).unwrap();
+        assert_reload_keeps_config(path.clone());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn missing_config_is_not_reloaded() {
+        let path = env::temp_dir().join(format!("simple_wm_missing_{}.json", process::id()));
+        assert_reload_keeps_config(path);
+    }
+
+    fn move_resizes(wm: &WindowManager<FakeBackend>) -> Vec<Call> {
+        wm.window_system
+            .take_calls()
+            .into_iter()
+            .filter(|call| matches!(call, Call::MoveResize { .. }))
+            .collect()
+    }
+
+    #[test]
+    fn only_changed_geometry_is_applied() {
+        let mut wm = window_manager();
+        // every client moves its frame & the window within
+        manage(&mut wm, 2);
+        assert_eq!(move_resizes(&wm).len(), 2);
+
+        // the master shrinks, the new client appears next to it
+        manage(&mut wm, 3);
+        assert_eq!(move_resizes(&wm).len(), 4);
+
+        wm.resize_current();
+        assert!(move_resizes(&wm).is_empty());
+
+        // swapping both clients moves both of them
+        wm.rearrange_current(Tag::zoom);
+        assert_eq!(move_resizes(&wm).len(), 4);
+    }
+
+    #[test]
+    fn shifting_keeps_a_minimum_column_width() {
+        let mut wm = window_manager();
+        manage(&mut wm, 2);
+        manage(&mut wm, 3);
+
+        wm.shift_master(2000);
+        assert_eq!(wm.tags[0].get_windows()[0].width, MIN_COLUMN_WIDTH);
+        wm.shift_master(-2000);
+        assert_eq!(wm.tags[0].get_windows()[1].width, MIN_COLUMN_WIDTH);
+    }
+
+    #[test]
+    fn monocle_raises_the_focused_client() {
+        let mut wm = window_manager();
+        wm.tags[0].set_layout(LayoutKind::Monocle);
+        manage(&mut wm, 2);
+        manage(&mut wm, 3);
+        manage(&mut wm, 4);
+        let layout = wm.window_system.atoms.simple_wm_layout;
+        let calls = wm.window_system.take_calls();
+        assert!(calls.contains(&Call::SetTextProperty(ROOT, layout, "[3]".to_string())));
+
+        wm.focus_relative(true);
+        let calls = wm.window_system.take_calls();
+        assert_eq!(calls[..2], [Call::Focus(3), Call::Raise(frame(&wm, 3))]);
+
+        // every client is laid out on top of each other
+        let bounds: HashSet<_> = wm.tags[0]
+            .get_windows()
+            .iter()
+            .map(Client::bounds)
+            .collect();
+        assert_eq!(bounds.len(), 1);
+
+        wm.on_destroy_notify(&4);
+        let calls = wm.window_system.take_calls();
+        assert!(calls.contains(&Call::SetTextProperty(ROOT, layout, "[2]".to_string())));
+    }
+
+    #[test]
+    fn every_tag_has_its_own_layout() {
+        let mut config = Config::new();
+        config.layout = LayoutKind::Grid;
+        config.workspace_layouts.insert(1, LayoutKind::Monocle);
+        let mut wm = window_manager_with(config);
+        assert_eq!(wm.tags[0].layout.kind(), LayoutKind::Grid);
+        assert_eq!(wm.tags[1].layout.kind(), LayoutKind::Monocle);
+
+        // Super+t only affects the current tag
+        press(&mut wm, xlib::Mod4Mask, keysym::XK_t);
+        assert_eq!(wm.tags[0].layout.kind(), LayoutKind::Stack);
+        assert_eq!(wm.tags[1].layout.kind(), LayoutKind::Monocle);
+
+        wm.view_workspace(1);
+        assert_eq!(wm.layout_symbol, "[0]");
+        wm.view_workspace(0);
+        assert_eq!(wm.layout_symbol, "[]=");
+    }
+
+    #[test]
+    fn cycling_through_the_configured_layouts() {
+        let mut wm = window_manager();
+        wm.config.layouts = vec![LayoutKind::Stack, LayoutKind::Grid];
+
+        wm.cycle_layout();
+        assert_eq!(wm.tags[0].layout.kind(), LayoutKind::Grid);
+        wm.cycle_layout();
+        assert_eq!(wm.tags[0].layout.kind(), LayoutKind::Stack);
+
+        // a layout outside of the list starts over
+        wm.set_layout(LayoutKind::Spiral);
+        wm.cycle_layout();
+        assert_eq!(wm.tags[0].layout.kind(), LayoutKind::Stack);
+    }
+
+    #[test]
+    fn adjusting_the_master_count() {
+        let mut config = Config::new();
+        config.workspace_master_counts.insert(0, 2);
+        let mut wm = window_manager_with(config);
+        assert_eq!(wm.tags[0].master_count, 2);
+        assert_eq!(wm.tags[1].master_count, 1);
+        for window in 2..5 {
+            manage(&mut wm, window);
+        }
+        // both masters share the left column
+        let master_width = wm.tags[0].get_windows()[2].width;
+        assert_eq!(wm.tags[0].get_windows()[1].width, master_width);
+
+        press(&mut wm, xlib::Mod4Mask, keysym::XK_d);
+        press(&mut wm, xlib::Mod4Mask, keysym::XK_d);
+        press(&mut wm, xlib::Mod4Mask, keysym::XK_d);
+        assert_eq!(wm.tags[0].master_count, 0);
+        press(&mut wm, xlib::Mod4Mask, keysym::XK_i);
+        assert_eq!(wm.tags[0].master_count, 1);
+        assert_eq!(wm.tags[1].master_count, 1);
+
+        // there are never more masters than clients
+        for _ in 0..5 {
+            press(&mut wm, xlib::Mod4Mask, keysym::XK_i);
+        }
+        assert_eq!(wm.tags[0].master_count, 3);
+        press(&mut wm, xlib::Mod4Mask, keysym::XK_d);
+        assert_eq!(wm.tags[0].master_count, 2);
+    }
+
+    #[test]
+    fn clients_are_reparented_into_frames() {
+        let mut wm = window_manager();
+        manage(&mut wm, 2);
+        let client = wm.tags[0].get_client(&2).cloned().unwrap();
+        assert_eq!(client.frame, FIRST_FRAME);
+        assert_eq!(client.border_width, 2);
+
+        let calls = wm.window_system.take_calls();
+        let reparent = Call::Reparent {
+            window: 2,
+            parent: FIRST_FRAME,
+            x: 0,
+            y: 0,
+        };
+        assert!(calls.contains(&Call::AddToSaveSet(2)));
+        assert!(calls.contains(&reparent));
+        // the frame includes the border, the window is moved within it
+        assert!(calls.contains(&Call::MoveResize {
+            window: FIRST_FRAME,
+            x: 8,
+            y: 8,
+            width: 980,
+            height: 780
+        }));
+        assert!(calls.contains(&Call::MoveResize {
+            window: 2,
+            x: 0,
+            y: 0,
+            width: 980,
+            height: 780
+        }));
+        assert!(calls.contains(&Call::ConfigureNotify(2, Rect::new(10, 10, 980, 780))));
+        assert!(calls.contains(&Call::Map(FIRST_FRAME)));
+    }
+
+    #[test]
+    fn frames_are_removed_on_unmanage() {
+        let mut wm = window_manager();
+        manage(&mut wm, 2);
+        manage(&mut wm, 3);
+        let frame_2 = frame(&wm, 2);
+        let frame_3 = frame(&wm, 3);
+        let bounds = wm.tags[0].get_client(&2).unwrap().window_bounds();
+        wm.window_system.take_calls();
+
+        // the client withdrew its window --> give it back to the root window
+        wm.on_unmap_notify(&2);
+        assert!(!wm.tags[0].window_contained(&2));
+        let calls = wm.window_system.take_calls();
+        assert!(calls.contains(&Call::Reparent {
+            window: 2,
+            parent: ROOT,
+            x: bounds.x,
+            y: bounds.y
+        }));
+        assert!(calls.contains(&Call::RemoveFromSaveSet(2)));
+        assert!(calls.contains(&Call::DestroyWindow(frame_2)));
+
+        // the frame being destroyed is no reason to refocus anything
+        wm.on_destroy_notify(&frame_2);
+        assert!(wm.window_system.take_calls().is_empty());
+
+        // a destroyed window can't be reparented anymore
+        wm.on_destroy_notify(&3);
+        assert_eq!(
+            wm.window_system.take_calls(),
+            vec![Call::DestroyWindow(frame_3)]
+        );
+    }
+
+    #[test]
+    fn border_colors() {
+        let mut wm = window_manager();
+        manage(&mut wm, 2);
+        manage(&mut wm, 3);
+        let config = Config::new();
+        assert_eq!(
+            wm.tags[0].get_client(&3).unwrap().border_color,
+            Some(config.focused_border_color)
+        );
+        assert_eq!(
+            wm.tags[0].get_client(&2).unwrap().border_color,
+            Some(config.border_color)
+        );
+
+        wm.window_system.take_calls();
+        wm.window_system.urgent.insert(2);
+        wm.on_property_notify(&2, xlib::XA_WM_HINTS);
+        assert_eq!(
+            wm.window_system.take_calls(),
+            vec![Call::SetBorderColor(
+                frame(&wm, 2),
+                config.urgent_border_color
+            )]
+        );
+
+        // focus wins over urgency
+        wm.focus_relative(true);
+        assert_eq!(
+            wm.tags[0].get_client(&2).unwrap().border_color,
+            Some(config.focused_border_color)
+        );
+    }
+
+    #[test]
+    fn title_bars() {
+        let mut config = Config::new();
+        config.title_bars = true;
+        config.urgent_border_color = config.focused_border_color;
+        let mut wm = window_manager_with(config);
+        wm.window_system.titles.insert(2, "editor".to_string());
+        manage(&mut wm, 2);
+        let calls = wm.window_system.take_calls();
+        // the window sits below the title bar within the frame
+        assert!(calls.contains(&Call::Reparent {
+            window: 2,
+            parent: FIRST_FRAME,
+            x: 0,
+            y: 18
+        }));
+        assert!(calls.contains(&Call::MoveResize {
+            window: FIRST_FRAME,
+            x: 8,
+            y: 8,
+            width: 980,
+            height: 780
+        }));
+        assert!(calls.contains(&Call::MoveResize {
+            window: 2,
+            x: 0,
+            y: 18,
+            width: 980,
+            height: 762
+        }));
+        assert!(calls.contains(&Call::DrawTitleBar {
+            frame: FIRST_FRAME,
+            width: 980,
+            height: 18,
+            title: "editor".to_string(),
+            background: wm.config.focused_border_color,
+            foreground: wm.config.focused_title_color,
+        }));
+
+        manage(&mut wm, 3);
+        wm.window_system
+            .titles
+            .insert(2, "editor - notes".to_string());
+        wm.window_system.take_calls();
+        wm.on_property_notify(&2, wm.window_system.atoms.net_wm_name);
+        assert_eq!(
+            wm.window_system.take_calls(),
+            vec![Call::DrawTitleBar {
+                frame: FIRST_FRAME,
+                width: 484,
+                height: 18,
+                title: "editor - notes".to_string(),
+                background: wm.config.border_color,
+                foreground: wm.config.title_color,
+            }]
+        );
+
+        // clicking the title bar focuses the client
+        wm.on_button_press(&FIRST_FRAME);
+        assert_eq!(wm.current_window, Some(2));
+
+        // urgent & focused borders look alike here, but the title still changes its color
+        wm.window_system.urgent.insert(3);
+        wm.on_property_notify(&3, xlib::XA_WM_HINTS);
+        wm.window_system.take_calls();
+        wm.on_button_press(&frame(&wm, 3));
+        let calls = wm.window_system.take_calls();
+        assert!(calls.contains(&Call::DrawTitleBar {
+            frame: frame(&wm, 3),
+            width: 484,
+            height: 18,
+            title: String::new(),
+            background: wm.config.focused_border_color,
+            foreground: wm.config.focused_title_color,
+        }));
+    }
+
+    #[test]
+    fn toggling_floating() {
+        let mut wm = window_manager();
+        manage(&mut wm, 2);
+        manage(&mut wm, 3);
+        press(&mut wm, xlib::Mod4Mask | xlib::ShiftMask, keysym::XK_space);
+        assert!(wm.tags[0].get_client(&3).unwrap().floating);
+        // the remaining client takes the whole screen
+        assert_eq!(
+            wm.tags[0].get_client(&2).unwrap().bounds(),
+            Rect::new(8, 8, 984, 784)
+        );
+
+        // a floating client gets the geometry it asks for
+        let mut event: xlib::XConfigureRequestEvent =
+            unsafe { MaybeUninit::zeroed().assume_init() };
+        event.window = 3;
+        event.value_mask = (xlib::CWX | xlib::CWY | xlib::CWWidth | xlib::CWHeight) as c_ulong;
+        event.x = 100;
+        event.y = 100;
+        event.width = 300;
+        event.height = 200;
+        wm.on_configure_request(event);
+        let floating_bounds = Rect::new(98, 98, 304, 204);
+        assert_eq!(wm.tags[0].get_client(&3).unwrap().bounds(), floating_bounds);
+
+        wm.toggle_floating();
+        assert!(!wm.tags[0].get_client(&3).unwrap().floating);
+        assert_eq!(wm.tags[0].get_client(&2).unwrap().width, 488);
+
+        // floating again restores the last floating geometry
+        wm.toggle_floating();
+        assert_eq!(wm.tags[0].get_client(&3).unwrap().bounds(), floating_bounds);
+    }
+
+    #[test]
+    fn dialogs_float_above_tiled_clients() {
+        let mut wm = window_manager();
+        manage(&mut wm, 2);
+        wm.window_system.transients.insert(3, 2);
+        wm.window_system
+            .geometries
+            .insert(3, Rect::new(0, 0, 400, 300));
+        manage(&mut wm, 3);
+        let dialog = wm.tags[0].get_client(&3).unwrap();
+        assert!(dialog.floating);
+        // without a position of its own, it is centered
+        assert_eq!(dialog.window_bounds(), Rect::new(300, 250, 400, 300));
+        assert_eq!(wm.layout_symbol, "[]=");
+
+        wm.set_layout(LayoutKind::Monocle);
+        wm.window_system.take_calls();
+        wm.focus_relative(true);
+        assert_eq!(
+            wm.window_system.take_calls()[..3],
+            [
+                Call::Focus(2),
+                Call::Raise(frame(&wm, 2)),
+                Call::Raise(frame(&wm, 3))
+            ]
+        );
+    }
+
+    #[test]
+    fn floating_windows_start_on_screen() {
+        let mut wm = window_manager();
+        wm.window_system.transients.insert(2, ROOT);
+        wm.window_system
+            .geometries
+            .insert(2, Rect::new(0, 0, 1200, 900));
+        manage(&mut wm, 2);
+        assert_eq!(
+            wm.tags[0].get_client(&2).unwrap().bounds(),
+            Rect::new(0, 0, 1000, 800)
+        );
+
+        wm.window_system.transients.insert(3, ROOT);
+        wm.window_system
+            .geometries
+            .insert(3, Rect::new(5000, -50, 200, 100));
+        manage(&mut wm, 3);
+        assert_eq!(
+            wm.tags[0].get_client(&3).unwrap().bounds(),
+            Rect::new(796, 0, 204, 104)
+        );
+    }
+
+    #[test]
+    fn new_dialogs_get_the_requested_geometry() {
+        let mut wm = window_manager();
+        manage(&mut wm, 2);
+        wm.window_system.transients.insert(3, 2);
+        wm.window_system
+            .geometries
+            .insert(3, Rect::new(0, 0, 400, 300));
+        wm.window_system.take_calls();
+
+        let mut event: xlib::XConfigureRequestEvent =
+            unsafe { MaybeUninit::zeroed().assume_init() };
+        event.window = 3;
+        event.value_mask = (xlib::CWX | xlib::CWY | xlib::CWWidth | xlib::CWHeight) as c_ulong;
+        event.x = 100;
+        event.y = 50;
+        event.width = 200;
+        event.height = 150;
+        wm.on_configure_request(event);
+
+        let requested = Rect::new(100, 50, 200, 150);
+        let dialog = wm.tags[0].get_client(&3).unwrap();
+        assert!(dialog.floating);
+        assert_eq!(dialog.window_bounds(), requested);
+        assert!(wm
+            .window_system
+            .take_calls()
+            .contains(&Call::ConfigureNotify(3, requested)));
+    }
+
+    #[test]
+    fn fixed_size_windows_float() {
+        let mut wm = window_manager();
+        let hints = SizeHints {
+            min_width: 300,
+            min_height: 200,
+            max_width: 300,
+            max_height: 200,
+            ..SizeHints::default()
+        };
+        wm.window_system.size_hints.insert(2, hints);
+        manage(&mut wm, 2);
+        let client = wm.tags[0].get_client(&2).unwrap();
+        assert!(client.floating);
+        assert_eq!(client.window_bounds(), Rect::new(350, 300, 300, 200));
+    }
+
+    #[test]
+    fn size_hints_of_tiled_clients() {
+        let mut wm = window_manager();
+        manage(&mut wm, 2);
+        let hints = SizeHints {
+            width_inc: 9,
+            height_inc: 14,
+            ..SizeHints::default()
+        };
+        wm.window_system.size_hints.insert(2, hints);
+        wm.on_property_notify(&2, xlib::XA_WM_NORMAL_HINTS);
+        let client = wm.tags[0].get_client(&2).unwrap();
+        assert_eq!(client.size_hints, hints);
+        assert_eq!(client.window_bounds(), Rect::new(10, 10, 980, 780));
+
+        // whole cells only, the window keeps its top left corner
+        wm.config.tiled_size_hints = true;
+        wm.on_property_notify(&2, xlib::XA_WM_NORMAL_HINTS);
+        let client = wm.tags[0].get_client(&2).unwrap();
+        assert_eq!(client.window_bounds(), Rect::new(10, 10, 972, 770));
+
+        // clients on hidden tags aren't laid out right away
+        manage(&mut wm, 3);
+        wm.move_to_workspace(1, false);
+        wm.window_system.take_calls();
+        wm.on_property_notify(&3, xlib::XA_WM_NORMAL_HINTS);
+        assert!(wm.window_system.take_calls().is_empty());
+    }
+
+    #[test]
+    fn windows_becoming_fixed_size_float() {
+        let mut wm = window_manager();
+        manage(&mut wm, 2);
+        manage(&mut wm, 3);
+        let hints = SizeHints {
+            min_width: 300,
+            min_height: 200,
+            max_width: 300,
+            max_height: 200,
+            ..SizeHints::default()
+        };
+        wm.window_system.size_hints.insert(3, hints);
+        wm.on_property_notify(&3, xlib::XA_WM_NORMAL_HINTS);
+        let client = wm.tags[0].get_client(&3).unwrap();
+        assert!(client.floating);
+        // it stays where it was, just at its fixed size
+        assert_eq!(client.window_bounds(), Rect::new(10, 10, 300, 200));
+        assert_eq!(
+            wm.tags[0].get_client(&2).unwrap().bounds(),
+            Rect::new(8, 8, 984, 784)
+        );
+    }
+
+    #[test]
+    fn releasing_clients() {
+        let mut wm = window_manager();
+        manage(&mut wm, 2);
+        manage(&mut wm, 3);
+        wm.move_to_workspace(1, false);
+        wm.window_system.take_calls();
+
+        wm.release_clients();
+        let calls = wm.window_system.take_calls();
+        for window in [2, 3].iter() {
+            assert!(calls.contains(&Call::RemoveFromSaveSet(*window)));
+        }
+        assert!(wm.tags.iter().all(|tag| tag.get_windows().is_empty()));
+    }
+}
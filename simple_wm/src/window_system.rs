@@ -1,28 +1,595 @@
+use crate::backend::Backend;
+use crate::models::{Color, Rect, SizeHints};
+use std::cell::Cell;
+use std::env;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::os::raw::{c_char, c_int, c_long, c_uchar, c_ulong};
 use std::ptr;
-use x11::xlib::{Display, Window};
+use std::slice;
+use x11::xft;
+use x11::xlib;
+use x11::xlib::{Atom, Display, KeySym, Window};
 use x11::xlib::{XDefaultScreenOfDisplay, XOpenDisplay, XRootWindowOfScreen};
-use std::os::raw::c_int;
+use x11::xrender::XRenderColor;
+
+/// Everything that can go wrong while connecting to the X server
+#[derive(Debug)]
+pub enum WindowSystemError {
+    /// Neither --display nor $DISPLAY were given
+    NoDisplay,
+    /// The display name contains a NUL byte
+    InvalidDisplayName(String),
+    /// XOpenDisplay failed for the display with the given name
+    CannotOpenDisplay(String),
+}
+
+impl fmt::Display for WindowSystemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WindowSystemError::NoDisplay => {
+                write!(f, "no display given, set $DISPLAY or pass --display")
+            }
+            WindowSystemError::InvalidDisplayName(name) => {
+                write!(f, "invalid display name {:?}", name)
+            }
+            WindowSystemError::CannotOpenDisplay(name) => {
+                write!(f, "cannot open display '{}'", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WindowSystemError {}
+
+/// The atoms simple_wm needs, interned once on startup
+pub struct Atoms {
+    pub wm_protocols: Atom,
+    pub wm_delete_window: Atom,
+    /// WM_Sn, owned by the window manager of screen n
+    pub wm_selection: Atom,
+    pub manager: Atom,
+    pub utf8_string: Atom,
+    pub net_wm_name: Atom,
+    /// Holds the symbol of the current layout, for status bars
+    pub simple_wm_layout: Atom,
+}
+
+impl Atoms {
+    fn intern(display: *mut Display, screen_number: c_int) -> Self {
+        Self {
+            wm_protocols: Self::intern_one(display, "WM_PROTOCOLS"),
+            wm_delete_window: Self::intern_one(display, "WM_DELETE_WINDOW"),
+            wm_selection: Self::intern_one(display, &format!("WM_S{}", screen_number)),
+            manager: Self::intern_one(display, "MANAGER"),
+            utf8_string: Self::intern_one(display, "UTF8_STRING"),
+            net_wm_name: Self::intern_one(display, "_NET_WM_NAME"),
+            simple_wm_layout: Self::intern_one(display, "_SIMPLE_WM_LAYOUT"),
+        }
+    }
+
+    fn intern_one(display: *mut Display, name: &str) -> Atom {
+        let name = CString::new(name).unwrap();
+        unsafe { xlib::XInternAtom(display, name.as_ptr(), xlib::False) }
+    }
+}
 
 pub struct WindowSystem {
     pub display: *mut Display,
     pub root: Window,
     pub width: c_int,
     pub height: c_int,
+    pub atoms: Atoms,
+    pub screen_number: c_int,
+    /// The font of the title bars, null as long as none is loaded
+    font: Cell<*mut xft::XftFont>,
 }
 
 impl WindowSystem {
-    pub fn new() -> WindowSystem {
+    /// Connects to the given display, or to $DISPLAY if there is none
+    pub fn new(display_name: Option<&str>) -> Result<WindowSystem, WindowSystemError> {
+        let c_name = match display_name {
+            Some(name) => Some(
+                CString::new(name)
+                    .map_err(|_| WindowSystemError::InvalidDisplayName(name.to_owned()))?,
+            ),
+            None => None,
+        };
+
         unsafe {
-            let display = XOpenDisplay(ptr::null_mut());
+            let display = XOpenDisplay(c_name.as_ref().map_or(ptr::null(), |name| name.as_ptr()));
+            if display.is_null() {
+                let name = display_name
+                    .map(str::to_owned)
+                    .or_else(|| env::var("DISPLAY").ok().filter(|name| !name.is_empty()));
+                return Err(match name {
+                    Some(name) => WindowSystemError::CannotOpenDisplay(name),
+                    None => WindowSystemError::NoDisplay,
+                });
+            }
             let screen = XDefaultScreenOfDisplay(display);
             let root = XRootWindowOfScreen(screen);
+            let screen_number = xlib::XScreenNumberOfScreen(screen);
 
-            WindowSystem {
-                display: display,
-                root: root,
+            Ok(WindowSystem {
+                display,
+                root,
                 width: (*screen).width,
                 height: (*screen).height,
+                atoms: Atoms::intern(display, screen_number),
+                screen_number,
+                font: Cell::new(ptr::null_mut()),
+            })
+        }
+    }
+
+    /// Fetches the current server time by appending nothing to a property of the window,
+    /// which needs to select PropertyChangeMask
+    pub fn server_time(&self, window: Window) -> xlib::Time {
+        unsafe {
+            xlib::XChangeProperty(
+                self.display,
+                window,
+                self.atoms.manager,
+                xlib::XA_STRING,
+                8,
+                xlib::PropModeAppend,
+                ptr::null(),
+                0,
+            );
+            let mut event: xlib::XEvent = std::mem::zeroed();
+            xlib::XWindowEvent(self.display, window, xlib::PropertyChangeMask, &mut event);
+            event.property.time
+        }
+    }
+
+    /// Reads a UTF8_STRING property of the window
+    fn utf8_property(&self, window: Window, property: Atom) -> Option<String> {
+        unsafe {
+            let mut actual_type: Atom = 0;
+            let mut actual_format: c_int = 0;
+            let mut count: c_ulong = 0;
+            let mut remaining: c_ulong = 0;
+            let mut data: *mut c_uchar = ptr::null_mut();
+            let status = xlib::XGetWindowProperty(
+                self.display,
+                window,
+                property,
+                0,
+                // in 32 bit units, that's 4kB of text
+                1024,
+                xlib::False,
+                self.atoms.utf8_string,
+                &mut actual_type,
+                &mut actual_format,
+                &mut count,
+                &mut remaining,
+                &mut data,
+            );
+            if status != xlib::Success as c_int || data.is_null() {
+                return None;
             }
+            let value = if actual_type == self.atoms.utf8_string && actual_format == 8 {
+                let bytes = slice::from_raw_parts(data, count as usize);
+                Some(String::from_utf8_lossy(bytes).into_owned())
+            } else {
+                None
+            };
+            xlib::XFree(data as *mut _);
+            value
+        }
+    }
+
+    /// Allocates a color for drawing with Xft; it has to be freed again
+    unsafe fn xft_color(&self, color: Color) -> xft::XftColor {
+        // 8 bit --> 16 bit per channel
+        let render_color = XRenderColor {
+            red: color.red() as u16 * 0x101,
+            green: color.green() as u16 * 0x101,
+            blue: color.blue() as u16 * 0x101,
+            alpha: 0xffff,
+        };
+        let mut xft_color: xft::XftColor = std::mem::zeroed();
+        xft::XftColorAllocValue(
+            self.display,
+            xlib::XDefaultVisual(self.display, self.screen_number),
+            xlib::XDefaultColormap(self.display, self.screen_number),
+            &render_color,
+            &mut xft_color,
+        );
+        xft_color
+    }
+
+    unsafe fn free_xft_color(&self, color: &mut xft::XftColor) {
+        xft::XftColorFree(
+            self.display,
+            xlib::XDefaultVisual(self.display, self.screen_number),
+            xlib::XDefaultColormap(self.display, self.screen_number),
+            color,
+        );
+    }
+}
+
+impl Backend for WindowSystem {
+    fn root(&self) -> Window {
+        self.root
+    }
+
+    fn screen_size(&self) -> (i32, i32) {
+        (self.width, self.height)
+    }
+
+    fn atoms(&self) -> &Atoms {
+        &self.atoms
+    }
+
+    fn move_resize(&self, window: Window, x: i32, y: i32, width: i32, height: i32) {
+        unsafe {
+            xlib::XMoveResizeWindow(self.display, window, x, y, width as u32, height as u32);
+        }
+    }
+
+    fn map(&self, window: Window) {
+        unsafe {
+            xlib::XMapWindow(self.display, window);
+        }
+    }
+
+    fn unmap(&self, window: Window) {
+        unsafe {
+            xlib::XUnmapWindow(self.display, window);
+        }
+    }
+
+    fn raise(&self, window: Window) {
+        unsafe {
+            xlib::XRaiseWindow(self.display, window);
+        }
+    }
+
+    fn focus(&self, window: Window) {
+        unsafe {
+            xlib::XSetInputFocus(
+                self.display,
+                window,
+                xlib::RevertToPointerRoot,
+                xlib::CurrentTime,
+            );
+        }
+    }
+
+    fn kill(&self, window: Window) {
+        unsafe {
+            xlib::XKillClient(self.display, window);
+        }
+    }
+
+    fn create_frame(&self, border_width: i32) -> Window {
+        unsafe {
+            let frame = xlib::XCreateSimpleWindow(
+                self.display,
+                self.root,
+                0,
+                0,
+                1,
+                1,
+                border_width as u32,
+                0,
+                0,
+            );
+            // requests & notifications of the client now come from the frame
+            // clicks & exposure are for the title bar
+            xlib::XSelectInput(
+                self.display,
+                frame,
+                xlib::SubstructureRedirectMask
+                    | xlib::SubstructureNotifyMask
+                    | xlib::ExposureMask
+                    | xlib::ButtonPressMask,
+            );
+            frame
+        }
+    }
+
+    fn destroy_window(&self, window: Window) {
+        unsafe {
+            xlib::XDestroyWindow(self.display, window);
+        }
+    }
+
+    fn reparent(&self, window: Window, parent: Window, x: i32, y: i32) {
+        unsafe {
+            xlib::XReparentWindow(self.display, window, parent, x, y);
+        }
+    }
+
+    fn add_to_save_set(&self, window: Window) {
+        unsafe {
+            xlib::XAddToSaveSet(self.display, window);
+        }
+    }
+
+    fn remove_from_save_set(&self, window: Window) {
+        unsafe {
+            xlib::XRemoveFromSaveSet(self.display, window);
+        }
+    }
+
+    fn set_border_width(&self, window: Window, width: i32) {
+        unsafe {
+            xlib::XSetWindowBorderWidth(self.display, window, width as u32);
+        }
+    }
+
+    fn set_border_color(&self, window: Window, color: Color) {
+        unsafe {
+            xlib::XSetWindowBorder(self.display, window, color.pixel());
+        }
+    }
+
+    fn send_configure_notify(&self, window: Window, bounds: Rect) {
+        unsafe {
+            let mut event: xlib::XEvent = std::mem::zeroed();
+            event.configure.type_ = xlib::ConfigureNotify;
+            event.configure.event = window;
+            event.configure.window = window;
+            event.configure.x = bounds.x;
+            event.configure.y = bounds.y;
+            event.configure.width = bounds.width;
+            event.configure.height = bounds.height;
+            xlib::XSendEvent(
+                self.display,
+                window,
+                xlib::False,
+                xlib::StructureNotifyMask,
+                &mut event,
+            );
+        }
+    }
+
+    fn watch_properties(&self, window: Window) {
+        unsafe {
+            xlib::XSelectInput(self.display, window, xlib::PropertyChangeMask);
+        }
+    }
+
+    fn is_urgent(&self, window: Window) -> bool {
+        unsafe {
+            let hints = xlib::XGetWMHints(self.display, window);
+            if hints.is_null() {
+                return false;
+            }
+            let urgent = (*hints).flags & xlib::XUrgencyHint != 0;
+            xlib::XFree(hints as *mut _);
+            urgent
+        }
+    }
+
+    fn transient_for(&self, window: Window) -> Option<Window> {
+        let mut parent: Window = 0;
+        let found = unsafe { xlib::XGetTransientForHint(self.display, window, &mut parent) };
+        if found != 0 && parent != 0 {
+            Some(parent)
+        } else {
+            None
+        }
+    }
+
+    fn size_hints(&self, window: Window) -> SizeHints {
+        let mut hints = SizeHints::default();
+        let size_hints = unsafe {
+            let mut size_hints: xlib::XSizeHints = std::mem::zeroed();
+            let mut supplied: c_long = 0;
+            if xlib::XGetWMNormalHints(self.display, window, &mut size_hints, &mut supplied) == 0 {
+                return hints;
+            }
+            size_hints
+        };
+        let flags = size_hints.flags;
+        if flags & xlib::PBaseSize != 0 {
+            hints.base_width = size_hints.base_width;
+            hints.base_height = size_hints.base_height;
+        }
+        if flags & xlib::PMinSize != 0 {
+            hints.min_width = size_hints.min_width;
+            hints.min_height = size_hints.min_height;
+        }
+        // the base & min size stand in for each other
+        if flags & xlib::PBaseSize == 0 {
+            hints.base_width = hints.min_width;
+            hints.base_height = hints.min_height;
+        }
+        if flags & xlib::PMinSize == 0 {
+            hints.min_width = hints.base_width;
+            hints.min_height = hints.base_height;
+        }
+        if flags & xlib::PMaxSize != 0 {
+            hints.max_width = size_hints.max_width;
+            hints.max_height = size_hints.max_height;
+        }
+        if flags & xlib::PResizeInc != 0 {
+            hints.width_inc = size_hints.width_inc;
+            hints.height_inc = size_hints.height_inc;
+        }
+        if flags & xlib::PAspect != 0 {
+            hints.min_aspect = (size_hints.min_aspect.x, size_hints.min_aspect.y);
+            hints.max_aspect = (size_hints.max_aspect.x, size_hints.max_aspect.y);
+        }
+        hints
+    }
+
+    fn window_geometry(&self, window: Window) -> Option<Rect> {
+        unsafe {
+            let mut attributes: xlib::XWindowAttributes = std::mem::zeroed();
+            if xlib::XGetWindowAttributes(self.display, window, &mut attributes) == 0 {
+                return None;
+            }
+            Some(Rect::new(
+                attributes.x,
+                attributes.y,
+                attributes.width,
+                attributes.height,
+            ))
+        }
+    }
+
+    fn keysym_to_keycode(&self, keysym: KeySym) -> u32 {
+        unsafe { xlib::XKeysymToKeycode(self.display, keysym).into() }
+    }
+
+    fn keycode_to_keysym(&self, keycode: u32) -> KeySym {
+        unsafe { xlib::XkbKeycodeToKeysym(self.display, keycode as xlib::KeyCode, 0, 0) }
+    }
+
+    fn grab_key(&self, keycode: u32, mask: u32, window: Window) {
+        unsafe {
+            xlib::XGrabKey(
+                self.display,
+                keycode as c_int,
+                mask,
+                window,
+                0,
+                xlib::GrabModeAsync,
+                xlib::GrabModeAsync,
+            );
+        }
+    }
+
+    fn ungrab_key(&self, keycode: u32, mask: u32, window: Window) {
+        unsafe {
+            xlib::XUngrabKey(self.display, keycode as c_int, mask, window);
+        }
+    }
+
+    fn set_text_property(&self, window: Window, property: Atom, value: &str) {
+        unsafe {
+            xlib::XChangeProperty(
+                self.display,
+                window,
+                property,
+                self.atoms.utf8_string,
+                8,
+                xlib::PropModeReplace,
+                value.as_ptr(),
+                value.len() as c_int,
+            );
+        }
+    }
+
+    fn window_title(&self, window: Window) -> String {
+        if let Some(title) = self.utf8_property(window, self.atoms.net_wm_name) {
+            return title;
+        }
+        unsafe {
+            let mut name: *mut c_char = ptr::null_mut();
+            if xlib::XFetchName(self.display, window, &mut name) == 0 || name.is_null() {
+                return String::new();
+            }
+            let title = CStr::from_ptr(name).to_string_lossy().into_owned();
+            xlib::XFree(name as *mut _);
+            title
+        }
+    }
+
+    fn load_font(&self, name: &str) -> bool {
+        let name = match CString::new(name) {
+            Ok(name) => name,
+            Err(_) => return false,
+        };
+        unsafe {
+            let font = xft::XftFontOpenName(self.display, self.screen_number, name.as_ptr());
+            if font.is_null() {
+                return false;
+            }
+            let old = self.font.replace(font);
+            if !old.is_null() {
+                xft::XftFontClose(self.display, old);
+            }
+        }
+        true
+    }
+
+    fn draw_title_bar(
+        &self,
+        frame: Window,
+        width: i32,
+        height: i32,
+        title: &str,
+        background: Color,
+        foreground: Color,
+    ) {
+        unsafe {
+            let draw = xft::XftDrawCreate(
+                self.display,
+                frame,
+                xlib::XDefaultVisual(self.display, self.screen_number),
+                xlib::XDefaultColormap(self.display, self.screen_number),
+            );
+            if draw.is_null() {
+                return;
+            }
+            let mut background = self.xft_color(background);
+            let mut foreground = self.xft_color(foreground);
+            xft::XftDrawRect(draw, &background, 0, 0, width as u32, height as u32);
+
+            let font = self.font.get();
+            if !font.is_null() {
+                // vertically centered, indented by half the font height
+                let text_height = (*font).ascent + (*font).descent;
+                let y = (height - text_height) / 2 + (*font).ascent;
+                let x = text_height / 2;
+                xft::XftDrawStringUtf8(
+                    draw,
+                    &foreground,
+                    font,
+                    x,
+                    y,
+                    title.as_ptr(),
+                    title.len() as c_int,
+                );
+            }
+
+            self.free_xft_color(&mut background);
+            self.free_xft_color(&mut foreground);
+            xft::XftDrawDestroy(draw);
+        }
+    }
+
+    fn supports_protocol(&self, window: Window, protocol: Atom) -> bool {
+        unsafe {
+            let mut protocols: *mut Atom = ptr::null_mut();
+            let mut count: c_int = 0;
+            if xlib::XGetWMProtocols(self.display, window, &mut protocols, &mut count) == 0 {
+                return false;
+            }
+            let supported = !protocols.is_null()
+                && slice::from_raw_parts(protocols, count as usize).contains(&protocol);
+            if !protocols.is_null() {
+                xlib::XFree(protocols as *mut _);
+            }
+            supported
+        }
+    }
+
+    fn send_protocol(&self, window: Window, protocol: Atom) {
+        unsafe {
+            let mut event: xlib::XEvent = std::mem::zeroed();
+            event.client_message.type_ = xlib::ClientMessage;
+            event.client_message.window = window;
+            event.client_message.message_type = self.atoms.wm_protocols;
+            event.client_message.format = 32;
+            event.client_message.data.set_long(0, protocol as c_long);
+            event
+                .client_message
+                .data
+                .set_long(1, xlib::CurrentTime as c_long);
+            xlib::XSendEvent(
+                self.display,
+                window,
+                xlib::False,
+                xlib::NoEventMask,
+                &mut event,
+            );
         }
     }
 }
@@ -30,6 +597,10 @@ impl WindowSystem {
 impl Drop for WindowSystem {
     fn drop(&mut self) {
         unsafe {
+            let font = self.font.get();
+            if !font.is_null() {
+                xft::XftFontClose(self.display, font);
+            }
             x11::xlib::XCloseDisplay(self.display);
         }
     }